@@ -3,26 +3,28 @@ fn main() {
         lxc::Container::new("apicontainer", None).expect("Failed to setup lxc_container struct");
 
     if !c.is_defined() {
-        c.create(
-            Some("download"),
-            None,
-            None,
-            lxc::CreateFlags::QUIET,
-            &["-d", "ubuntu", "-r", "focal", "-a", "amd64"],
-        )
-        .expect("Failed to create container rootfs");
+        let options = lxc::CreateOptions::new()
+            .template(lxc::create::Template::Download {
+                dist: "ubuntu".to_string(),
+                release: "focal".to_string(),
+                arch: "amd64".to_string(),
+            })
+            .quiet(true);
+
+        c.create_with(&options)
+            .expect("Failed to create container rootfs");
     }
 
     c.start(false, &[]).expect("Failed to start the container");
 
-    println!("Container state: {}", c.state());
+    println!("Container state: {}", c.state().unwrap_or_default());
     println!("Container PID: {}", c.init_pid());
     println!("Interfaces: {:?}", c.get_interfaces());
 
-    if c.shutdown(30).is_err() {
-        println!("Failed to cleanly shutdown the container, forcing.");
-        c.stop().expect("Failed to kill the container.");
-    }
+    let step = c
+        .stop_gracefully(&lxc::StopPolicy::default())
+        .expect("Failed to kill the container.");
+    println!("Stopped by: {step:?}");
 
     c.destroy().expect("Failed to destroy the container.");
 }
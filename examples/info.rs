@@ -1,6 +1,9 @@
 fn main() -> lxc::Result {
-    println!("LXC version: {}", lxc::version());
-    println!("LXC path: {}", lxc::path().unwrap_or("?".to_string()),);
+    println!("LXC version: {}", lxc::version()?);
+    println!(
+        "LXC path: {}",
+        lxc::path().map_or("?".into(), |path| path.display().to_string())
+    );
     println!();
 
     let path = "/var/lib/lxc";
@@ -11,7 +14,7 @@ fn main() -> lxc::Result {
     println!();
 
     println!("Wait states:");
-    for state in lxc::wait_states() {
+    for state in lxc::wait_states()? {
         println!("- {state}");
     }
 
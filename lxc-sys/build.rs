@@ -1,6 +1,18 @@
 fn main() {
-    println!("cargo:rustc-link-lib=lxc");
+    if std::env::var_os("CARGO_FEATURE_DLOPEN").is_none() {
+        println!("cargo:rustc-link-lib=lxc");
+    }
 
+    let bindings = bindings();
+
+    println!("cargo:rustc-env=LXC_SYS_BINDINGS={bindings}");
+}
+
+/**
+ * Generate bindings from the installed `lxc/lxccontainer.h` header.
+ */
+#[cfg(feature = "bindgen")]
+fn bindings() -> String {
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
         .generate()
@@ -8,8 +20,29 @@ fn main() {
 
     let out_dir = std::env::var("OUT_DIR").unwrap();
 
-    let out_path = std::path::PathBuf::from(out_dir);
+    let out_path = std::path::PathBuf::from(out_dir).join("bindings.rs");
     bindings
-        .write_to_file(out_path.join("bindings.rs"))
+        .write_to_file(&out_path)
         .expect("Couldn't write bindings!");
+
+    out_path.display().to_string()
+}
+
+/**
+ * Select the pregenerated bindings matching the `v*` features.
+ */
+#[cfg(not(feature = "bindgen"))]
+fn bindings() -> String {
+    let major = [
+        ("V6_0", 6),
+        ("V5_0", 5),
+        ("V4_0", 4),
+        ("V3_0", 3),
+        ("V2_0", 2),
+    ]
+    .iter()
+    .find(|(feature, _)| std::env::var_os(format!("CARGO_FEATURE_{feature}")).is_some())
+    .map_or(1, |(_, major)| *major);
+
+    format!("bindings/v{major}.rs")
 }
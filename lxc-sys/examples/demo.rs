@@ -10,7 +10,7 @@ fn main() {
         /* Setup container struct */
         let c = lxc_sys::lxc_container_new(str!("apicontainer").as_ptr(), null());
 
-        if c == null_mut() {
+        if c.is_null() {
             panic(c, "Failed to setup lxc_container struct");
         }
 
@@ -31,7 +31,7 @@ fn main() {
             str!("trusty").as_ptr(),
             str!("-a").as_ptr(),
             str!("i386").as_ptr(),
-            null() as *const i8,
+            null::<std::os::raw::c_char>(),
         ) {
             panic(c, "Failed to create container rootfs");
         }
@@ -64,6 +64,6 @@ fn main() {
 }
 
 unsafe fn panic(c: *mut lxc_container, message: &str) -> ! {
-    lxc_sys::lxc_container_put(c);
+    unsafe { lxc_sys::lxc_container_put(c) };
     panic!("{}", message);
 }
@@ -0,0 +1,423 @@
+/* liblxc 1.1 bindings, regenerate with the `bindgen` feature. */
+
+pub const LXC_VERSION_MAJOR: u32 = 1;
+pub const LXC_VERSION_MINOR: u32 = 1;
+pub const LXC_VERSION_MICRO: u32 = 0;
+pub const LXC_VERSION: &[u8; 6] = b"1.1.0\0";
+pub const LXC_DEVEL: u32 = 0;
+pub const LXC_CLONE_KEEPNAME: u32 = 1;
+pub const LXC_CLONE_KEEPMACADDR: u32 = 2;
+pub const LXC_CLONE_SNAPSHOT: u32 = 4;
+pub const LXC_CLONE_KEEPBDEVTYPE: u32 = 8;
+pub const LXC_CLONE_MAYBE_SNAPSHOT: u32 = 16;
+pub const LXC_CLONE_MAXFLAGS: u32 = 32;
+pub const LXC_CREATE_QUIET: u32 = 1;
+pub const LXC_CREATE_MAXFLAGS: u32 = 2;
+pub type __uint64_t = u64;
+pub type __uid_t = ::std::os::raw::c_uint;
+pub type __gid_t = ::std::os::raw::c_uint;
+pub type __pid_t = ::std::os::raw::c_int;
+pub type gid_t = __gid_t;
+pub type uid_t = __uid_t;
+pub type pid_t = __pid_t;
+unsafe extern "C" {
+    pub fn free(__ptr: *mut ::std::os::raw::c_void);
+}
+pub const lxc_attach_env_policy_t_LXC_ATTACH_KEEP_ENV: lxc_attach_env_policy_t = 0;
+pub const lxc_attach_env_policy_t_LXC_ATTACH_CLEAR_ENV: lxc_attach_env_policy_t = 1;
+pub type lxc_attach_env_policy_t = ::std::os::raw::c_uint;
+pub const LXC_ATTACH_MOVE_TO_CGROUP: _bindgen_ty_1 = 1;
+pub const LXC_ATTACH_DROP_CAPABILITIES: _bindgen_ty_1 = 2;
+pub const LXC_ATTACH_SET_PERSONALITY: _bindgen_ty_1 = 4;
+pub const LXC_ATTACH_LSM_EXEC: _bindgen_ty_1 = 8;
+pub const LXC_ATTACH_REMOUNT_PROC_SYS: _bindgen_ty_1 = 65536;
+pub const LXC_ATTACH_LSM_NOW: _bindgen_ty_1 = 131072;
+pub const LXC_ATTACH_DEFAULT: _bindgen_ty_1 = 65535;
+pub type _bindgen_ty_1 = ::std::os::raw::c_uint;
+pub type lxc_attach_exec_t = ::std::option::Option<
+    unsafe extern "C" fn(payload: *mut ::std::os::raw::c_void) -> ::std::os::raw::c_int,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lxc_attach_options_t {
+    pub attach_flags: ::std::os::raw::c_int,
+    pub namespaces: ::std::os::raw::c_int,
+    pub personality: ::std::os::raw::c_long,
+    pub initial_cwd: *mut ::std::os::raw::c_char,
+    pub uid: uid_t,
+    pub gid: gid_t,
+    pub env_policy: lxc_attach_env_policy_t,
+    pub extra_env_vars: *mut *mut ::std::os::raw::c_char,
+    pub extra_keep_env: *mut *mut ::std::os::raw::c_char,
+    pub stdin_fd: ::std::os::raw::c_int,
+    pub stdout_fd: ::std::os::raw::c_int,
+    pub stderr_fd: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lxc_attach_command_t {
+    pub program: *mut ::std::os::raw::c_char,
+    pub argv: *mut *mut ::std::os::raw::c_char,
+}
+unsafe extern "C" {
+    pub fn lxc_attach_run_command(payload: *mut ::std::os::raw::c_void) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn lxc_attach_run_shell(payload: *mut ::std::os::raw::c_void) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lxc_lock {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lxc_conf {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lxc_container {
+    pub name: *mut ::std::os::raw::c_char,
+    pub configfile: *mut ::std::os::raw::c_char,
+    pub pidfile: *mut ::std::os::raw::c_char,
+    pub slock: *mut lxc_lock,
+    pub privlock: *mut lxc_lock,
+    pub numthreads: ::std::os::raw::c_int,
+    pub lxc_conf: *mut lxc_conf,
+    pub error_string: *mut ::std::os::raw::c_char,
+    pub error_num: ::std::os::raw::c_int,
+    pub daemonize: bool,
+    pub config_path: *mut ::std::os::raw::c_char,
+    pub is_defined: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub state: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container) -> *const ::std::os::raw::c_char,
+    >,
+    pub is_running: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub freeze: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub unfreeze: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub init_pid: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> pid_t>,
+    pub load_config: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, alt_file: *const ::std::os::raw::c_char) -> bool,
+    >,
+    pub start: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            useinit: ::std::os::raw::c_int,
+            argv: *const *mut ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub startl: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, useinit: ::std::os::raw::c_int, ...) -> bool,
+    >,
+    pub stop: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub want_daemonize:
+        ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container, state: bool) -> bool>,
+    pub want_close_all_fds:
+        ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container, state: bool) -> bool>,
+    pub config_file_name: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container) -> *mut ::std::os::raw::c_char,
+    >,
+    pub wait: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            state: *const ::std::os::raw::c_char,
+            timeout: ::std::os::raw::c_int,
+        ) -> bool,
+    >,
+    pub set_config_item: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            key: *const ::std::os::raw::c_char,
+            value: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub destroy: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub save_config: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, alt_file: *const ::std::os::raw::c_char) -> bool,
+    >,
+    pub create: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            t: *const ::std::os::raw::c_char,
+            bdevtype: *const ::std::os::raw::c_char,
+            specs: *mut bdev_specs,
+            flags: ::std::os::raw::c_int,
+            argv: *const *mut ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub createl: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            t: *const ::std::os::raw::c_char,
+            bdevtype: *const ::std::os::raw::c_char,
+            specs: *mut bdev_specs,
+            flags: ::std::os::raw::c_int,
+            ...
+        ) -> bool,
+    >,
+    pub rename: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, newname: *const ::std::os::raw::c_char) -> bool,
+    >,
+    pub reboot: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub shutdown: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, timeout: ::std::os::raw::c_int) -> bool,
+    >,
+    pub clear_config: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container)>,
+    pub clear_config_item: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, key: *const ::std::os::raw::c_char) -> bool,
+    >,
+    pub get_config_item: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            key: *const ::std::os::raw::c_char,
+            retv: *mut ::std::os::raw::c_char,
+            inlen: ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub get_running_config_item: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            key: *const ::std::os::raw::c_char,
+        ) -> *mut ::std::os::raw::c_char,
+    >,
+    pub get_keys: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            key: *const ::std::os::raw::c_char,
+            retv: *mut ::std::os::raw::c_char,
+            inlen: ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub get_interfaces: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container) -> *mut *mut ::std::os::raw::c_char,
+    >,
+    pub get_ips: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            interface: *const ::std::os::raw::c_char,
+            family: *const ::std::os::raw::c_char,
+            scope: ::std::os::raw::c_int,
+        ) -> *mut *mut ::std::os::raw::c_char,
+    >,
+    pub get_cgroup_item: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            subsys: *const ::std::os::raw::c_char,
+            retv: *mut ::std::os::raw::c_char,
+            inlen: ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub set_cgroup_item: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            subsys: *const ::std::os::raw::c_char,
+            value: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub get_config_path: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container) -> *const ::std::os::raw::c_char,
+    >,
+    pub set_config_path: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, path: *const ::std::os::raw::c_char) -> bool,
+    >,
+    pub clone: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            newname: *const ::std::os::raw::c_char,
+            lxcpath: *const ::std::os::raw::c_char,
+            flags: ::std::os::raw::c_int,
+            bdevtype: *const ::std::os::raw::c_char,
+            bdevdata: *const ::std::os::raw::c_char,
+            newsize: u64,
+            hookargs: *mut *mut ::std::os::raw::c_char,
+        ) -> *mut lxc_container,
+    >,
+    pub console_getfd: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            ttynum: *mut ::std::os::raw::c_int,
+            ptxfd: *mut ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub console: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            ttynum: ::std::os::raw::c_int,
+            stdinfd: ::std::os::raw::c_int,
+            stdoutfd: ::std::os::raw::c_int,
+            stderrfd: ::std::os::raw::c_int,
+            escape: ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub attach: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            exec_function: lxc_attach_exec_t,
+            exec_payload: *mut ::std::os::raw::c_void,
+            options: *mut lxc_attach_options_t,
+            attached_process: *mut pid_t,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub attach_run_wait: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            options: *mut lxc_attach_options_t,
+            program: *const ::std::os::raw::c_char,
+            argv: *const *const ::std::os::raw::c_char,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub attach_run_waitl: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            options: *mut lxc_attach_options_t,
+            program: *const ::std::os::raw::c_char,
+            arg: *const ::std::os::raw::c_char,
+            ...
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub snapshot: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            commentfile: *const ::std::os::raw::c_char,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub snapshot_list: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            snapshots: *mut *mut lxc_snapshot,
+        ) -> ::std::os::raw::c_int,
+    >,
+    pub snapshot_restore: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            snapname: *const ::std::os::raw::c_char,
+            newname: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub snapshot_destroy: ::std::option::Option<
+        unsafe extern "C" fn(c: *mut lxc_container, snapname: *const ::std::os::raw::c_char) -> bool,
+    >,
+    pub may_control: ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub add_device_node: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            src_path: *const ::std::os::raw::c_char,
+            dest_path: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub remove_device_node: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            src_path: *const ::std::os::raw::c_char,
+            dest_path: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub attach_interface: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            dev: *const ::std::os::raw::c_char,
+            dst_dev: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub detach_interface: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            dev: *const ::std::os::raw::c_char,
+            dst_dev: *const ::std::os::raw::c_char,
+        ) -> bool,
+    >,
+    pub checkpoint: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            directory: *mut ::std::os::raw::c_char,
+            stop: bool,
+            verbose: bool,
+        ) -> bool,
+    >,
+    pub restore: ::std::option::Option<
+        unsafe extern "C" fn(
+            c: *mut lxc_container,
+            directory: *mut ::std::os::raw::c_char,
+            verbose: bool,
+        ) -> bool,
+    >,
+    pub destroy_with_snapshots:
+        ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+    pub snapshot_destroy_all:
+        ::std::option::Option<unsafe extern "C" fn(c: *mut lxc_container) -> bool>,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lxc_snapshot {
+    pub name: *mut ::std::os::raw::c_char,
+    pub comment_pathname: *mut ::std::os::raw::c_char,
+    pub timestamp: *mut ::std::os::raw::c_char,
+    pub lxcpath: *mut ::std::os::raw::c_char,
+    pub free: ::std::option::Option<unsafe extern "C" fn(s: *mut lxc_snapshot)>,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct bdev_specs {
+    pub fstype: *mut ::std::os::raw::c_char,
+    pub fssize: u64,
+    pub zfs: bdev_specs__bindgen_ty_1,
+    pub lvm: bdev_specs__bindgen_ty_2,
+    pub dir: *mut ::std::os::raw::c_char,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct bdev_specs__bindgen_ty_1 {
+    pub zfsroot: *mut ::std::os::raw::c_char,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct bdev_specs__bindgen_ty_2 {
+    pub vg: *mut ::std::os::raw::c_char,
+    pub lv: *mut ::std::os::raw::c_char,
+    pub thinpool: *mut ::std::os::raw::c_char,
+}
+unsafe extern "C" {
+    pub fn lxc_container_new(
+        name: *const ::std::os::raw::c_char,
+        configpath: *const ::std::os::raw::c_char,
+    ) -> *mut lxc_container;
+}
+unsafe extern "C" {
+    pub fn lxc_container_get(c: *mut lxc_container) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn lxc_container_put(c: *mut lxc_container) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn lxc_get_wait_states(states: *mut *const ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn lxc_get_global_config_item(
+        key: *const ::std::os::raw::c_char,
+    ) -> *const ::std::os::raw::c_char;
+}
+unsafe extern "C" {
+    pub fn lxc_get_version() -> *const ::std::os::raw::c_char;
+}
+unsafe extern "C" {
+    pub fn list_defined_containers(
+        lxcpath: *const ::std::os::raw::c_char,
+        names: *mut *mut *mut ::std::os::raw::c_char,
+        cret: *mut *mut *mut lxc_container,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn list_active_containers(
+        lxcpath: *const ::std::os::raw::c_char,
+        names: *mut *mut *mut ::std::os::raw::c_char,
+        cret: *mut *mut *mut lxc_container,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn list_all_containers(
+        lxcpath: *const ::std::os::raw::c_char,
+        names: *mut *mut *mut ::std::os::raw::c_char,
+        cret: *mut *mut *mut lxc_container,
+    ) -> ::std::os::raw::c_int;
+}
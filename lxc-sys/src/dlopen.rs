@@ -0,0 +1,210 @@
+use super::bindings::lxc_container;
+#[cfg(feature = "v2_1")]
+use super::bindings::lxc_log;
+use std::os::raw::{c_char, c_int, c_void};
+
+/**
+ * Names tried, in order, to find liblxc.
+ */
+const LIBRARY_NAMES: &[&str] = &["liblxc.so.1", "liblxc.so"];
+
+/**
+ * Oldest liblxc providing the API selected by the `v*` features.
+ */
+const MINIMUM_VERSION: (u32, u32) = if cfg!(feature = "v6_0") {
+    (6, 0)
+} else if cfg!(feature = "v5_0") {
+    (5, 0)
+} else if cfg!(feature = "v4_0") {
+    (4, 0)
+} else if cfg!(feature = "v3_2") {
+    (3, 2)
+} else if cfg!(feature = "v3_1") {
+    (3, 1)
+} else if cfg!(feature = "v3_0") {
+    (3, 0)
+} else if cfg!(feature = "v2_1") {
+    (2, 1)
+} else if cfg!(feature = "v2_0") {
+    (2, 0)
+} else if cfg!(feature = "v1_1") {
+    (1, 1)
+} else if cfg!(feature = "v1_0") {
+    (1, 0)
+} else {
+    (0, 0)
+};
+
+static LIBRARY: std::sync::OnceLock<Result<Library, String>> = std::sync::OnceLock::new();
+
+/**
+ * Load liblxc.
+ *
+ * Every function of this crate loads the library on first call. If it or the
+ * function is missing, the function fails like liblxc does, returning `-1`,
+ * `NULL` or `false`. Call this function first to know why.
+ *
+ * A liblxc older than the version selected by the `v*` features is refused:
+ * its `lxc_container` struct is smaller than the one of the bindings.
+ */
+pub fn load() -> Result<(), String> {
+    library().map(|_| ())
+}
+
+fn library() -> Result<&'static Library, String> {
+    LIBRARY
+        .get_or_init(|| {
+            let mut errors = Vec::new();
+
+            for name in LIBRARY_NAMES {
+                match unsafe { Library::open(name) } {
+                    Ok(library) => return check_version(library),
+                    Err(err) => errors.push(err.to_string()),
+                }
+            }
+
+            Err(format!("Unable to load liblxc: {}", errors.join(", ")))
+        })
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+fn check_version(library: Library) -> Result<Library, String> {
+    let version = library
+        .lxc_get_version
+        .map(|f| unsafe { f() })
+        .filter(|version| !version.is_null())
+        .map(|version| unsafe { std::ffi::CStr::from_ptr(version) }.to_string_lossy())
+        .ok_or("Unable to determine liblxc version")?;
+
+    let (major, minor) = MINIMUM_VERSION;
+
+    if parse_version(&version) < MINIMUM_VERSION {
+        return Err(format!(
+            "liblxc {version} is too old, {major}.{minor} or later is required"
+        ));
+    }
+
+    Ok(library)
+}
+
+/**
+ * Parse the `major.minor` part of a liblxc version.
+ */
+fn parse_version(version: &str) -> (u32, u32) {
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|x| x.parse().unwrap_or_default());
+
+    (
+        numbers.next().unwrap_or_default(),
+        numbers.next().unwrap_or_default(),
+    )
+}
+
+/**
+ * Value returned by a function missing from liblxc.
+ */
+trait Failure {
+    fn failure() -> Self;
+}
+
+impl Failure for () {
+    fn failure() -> Self {}
+}
+
+impl Failure for c_int {
+    fn failure() -> Self {
+        -1
+    }
+}
+
+impl Failure for bool {
+    fn failure() -> Self {
+        false
+    }
+}
+
+impl<T> Failure for *const T {
+    fn failure() -> Self {
+        std::ptr::null()
+    }
+}
+
+impl<T> Failure for *mut T {
+    fn failure() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+macro_rules! dynamic {
+    ( $( $( #[$meta:meta] )* fn $name:ident( $( $arg:ident: $ty:ty ),* ) $( -> $ret:ty )?; )* ) => {
+        struct Library {
+            $(
+                $( #[$meta] )*
+                $name: Option<unsafe extern "C" fn( $( $ty ),* ) $( -> $ret )?>,
+            )*
+            _library: libloading::Library,
+        }
+
+        impl Library {
+            unsafe fn open(name: &str) -> Result<Self, libloading::Error> {
+                let library = unsafe { libloading::Library::new(name)? };
+
+                Ok(Self {
+                    $(
+                        $( #[$meta] )*
+                        $name: unsafe {
+                            library.get(concat!(stringify!($name), "\0").as_bytes())
+                                .ok()
+                                .map(|symbol| *symbol)
+                        },
+                    )*
+                    _library: library,
+                })
+            }
+        }
+
+        $(
+            $( #[$meta] )*
+            #[allow(clippy::missing_safety_doc)]
+            pub unsafe extern "C" fn $name( $( $arg: $ty ),* ) $( -> $ret )? {
+                match library().ok().and_then(|library| library.$name) {
+                    Some(f) => unsafe { f( $( $arg ),* ) },
+                    None => Failure::failure(),
+                }
+            }
+        )*
+    };
+}
+
+dynamic! {
+    fn lxc_container_new(name: *const c_char, configpath: *const c_char) -> *mut lxc_container;
+    fn lxc_container_get(c: *mut lxc_container) -> c_int;
+    fn lxc_container_put(c: *mut lxc_container) -> c_int;
+    fn lxc_get_wait_states(states: *mut *const c_char) -> c_int;
+    fn lxc_get_global_config_item(key: *const c_char) -> *const c_char;
+    fn lxc_get_version() -> *const c_char;
+    fn list_defined_containers(lxcpath: *const c_char, names: *mut *mut *mut c_char, cret: *mut *mut *mut lxc_container) -> c_int;
+    fn list_active_containers(lxcpath: *const c_char, names: *mut *mut *mut c_char, cret: *mut *mut *mut lxc_container) -> c_int;
+    fn list_all_containers(lxcpath: *const c_char, names: *mut *mut *mut c_char, cret: *mut *mut *mut lxc_container) -> c_int;
+    #[cfg(feature = "v2_1")]
+    fn lxc_log_init(log: *mut lxc_log) -> c_int;
+    #[cfg(feature = "v2_1")]
+    fn lxc_log_close();
+    #[cfg(feature = "v2_1")]
+    fn lxc_config_item_is_supported(key: *const c_char) -> bool;
+    fn lxc_attach_run_command(payload: *mut c_void) -> c_int;
+    fn lxc_attach_run_shell(payload: *mut c_void) -> c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_version() {
+        assert_eq!(super::parse_version("6.0.1"), (6, 0));
+        assert_eq!(super::parse_version("5.0.0~git2209-g5a7b9ce67"), (5, 0));
+        assert_eq!(super::parse_version("4.0"), (4, 0));
+        assert_eq!(super::parse_version("devel"), (0, 0));
+    }
+}
@@ -7,4 +7,22 @@
 #![allow(clippy::ptr_offset_with_cast)]
 #![allow(clippy::unnecessary_operation)]
 
-include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+#[cfg_attr(feature = "dlopen", allow(dead_code))]
+mod bindings {
+    include!(env!("LXC_SYS_BINDINGS"));
+}
+
+pub use bindings::*;
+
+#[cfg(feature = "dlopen")]
+mod dlopen;
+
+#[cfg(feature = "dlopen")]
+pub use dlopen::{
+    list_active_containers, list_all_containers, list_defined_containers, load,
+    lxc_attach_run_command, lxc_attach_run_shell, lxc_container_get, lxc_container_new,
+    lxc_container_put, lxc_get_global_config_item, lxc_get_version, lxc_get_wait_states,
+};
+
+#[cfg(all(feature = "dlopen", feature = "v2_1"))]
+pub use dlopen::{lxc_config_item_is_supported, lxc_log_close, lxc_log_init};
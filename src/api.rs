@@ -0,0 +1,170 @@
+/**
+ * Container operations, implemented by [`crate::Container`] and
+ * [`crate::MockContainer`].
+ *
+ * Write orchestration code against this trait to test it without liblxc.
+ */
+pub trait ContainerApi {
+    /** See [`crate::Container::is_defined()`]. */
+    fn is_defined(&self) -> bool;
+
+    /** See [`crate::Container::state()`]. */
+    fn state(&self) -> Option<String>;
+
+    /** See [`crate::Container::is_running()`]. */
+    fn is_running(&self) -> bool;
+
+    /** See [`crate::Container::init_pid()`]. */
+    fn init_pid(&self) -> i32;
+
+    /** See [`crate::Container::start()`]. */
+    fn start(&self, use_init: bool, argv: &[&str]) -> crate::Result;
+
+    /** See [`crate::Container::stop()`]. */
+    fn stop(&self) -> crate::Result;
+
+    /** See [`crate::Container::shutdown()`]. */
+    fn shutdown(&self, timeout: i32) -> crate::Result;
+
+    /** See [`crate::Container::reboot()`]. */
+    fn reboot(&self) -> crate::Result;
+
+    /** See [`crate::Container::signal()`]. */
+    fn signal(&self, signal: crate::Signal) -> crate::Result;
+
+    /** See [`crate::Container::freeze()`]. */
+    fn freeze(&self) -> crate::Result;
+
+    /** See [`crate::Container::unfreeze()`]. */
+    fn unfreeze(&self) -> crate::Result;
+
+    /** See [`crate::Container::wait()`]. */
+    fn wait(&self, state: &str, timeout: i32) -> crate::Result;
+
+    /** See [`crate::Container::destroy()`]. */
+    fn destroy(&self) -> crate::Result;
+
+    /** See [`crate::Container::get_config_item()`]. */
+    fn get_config_item(&self, key: &str) -> crate::Result<Option<String>>;
+
+    /** See [`crate::Container::set_config_item()`]. */
+    fn set_config_item(&self, key: &str, value: &str) -> crate::Result;
+
+    /** See [`crate::Container::clear_config_item()`]. */
+    fn clear_config_item(&self, key: &str) -> crate::Result;
+
+    /** See [`crate::Container::get_interfaces()`]. */
+    fn get_interfaces(&self) -> Vec<String>;
+
+    /** See [`crate::Container::get_ips()`]. */
+    fn get_ips(
+        &self,
+        interface: Option<&str>,
+        family: Option<&str>,
+        scope: std::os::raw::c_int,
+    ) -> crate::Result<Vec<std::net::IpAddr>>;
+
+    /** See [`crate::Container::attach_run_wait()`]. */
+    fn attach_run_wait(
+        &self,
+        options: &mut crate::attach::Options,
+        program: &str,
+        argv: &[&str],
+    ) -> crate::Result<i32>;
+
+    /** See [`crate::Container::stop_gracefully()`]. */
+    fn stop_gracefully(&self, policy: &crate::StopPolicy) -> crate::Result<Option<crate::StopStep>>
+    where
+        Self: Sized,
+    {
+        crate::stop::stop_gracefully(self, policy)
+    }
+}
+
+impl ContainerApi for crate::Container {
+    fn is_defined(&self) -> bool {
+        crate::Container::is_defined(self)
+    }
+
+    fn state(&self) -> Option<String> {
+        crate::Container::state(self)
+    }
+
+    fn is_running(&self) -> bool {
+        crate::Container::is_running(self)
+    }
+
+    fn init_pid(&self) -> i32 {
+        crate::Container::init_pid(self)
+    }
+
+    fn start(&self, use_init: bool, argv: &[&str]) -> crate::Result {
+        crate::Container::start(self, use_init, argv)
+    }
+
+    fn stop(&self) -> crate::Result {
+        crate::Container::stop(self)
+    }
+
+    fn shutdown(&self, timeout: i32) -> crate::Result {
+        crate::Container::shutdown(self, timeout)
+    }
+
+    fn reboot(&self) -> crate::Result {
+        crate::Container::reboot(self)
+    }
+
+    fn signal(&self, signal: crate::Signal) -> crate::Result {
+        crate::Container::signal(self, signal)
+    }
+
+    fn freeze(&self) -> crate::Result {
+        crate::Container::freeze(self)
+    }
+
+    fn unfreeze(&self) -> crate::Result {
+        crate::Container::unfreeze(self)
+    }
+
+    fn wait(&self, state: &str, timeout: i32) -> crate::Result {
+        crate::Container::wait(self, state, timeout)
+    }
+
+    fn destroy(&self) -> crate::Result {
+        crate::Container::destroy(self)
+    }
+
+    fn get_config_item(&self, key: &str) -> crate::Result<Option<String>> {
+        crate::Container::get_config_item(self, key)
+    }
+
+    fn set_config_item(&self, key: &str, value: &str) -> crate::Result {
+        crate::Container::set_config_item(self, key, value)
+    }
+
+    fn clear_config_item(&self, key: &str) -> crate::Result {
+        crate::Container::clear_config_item(self, key)
+    }
+
+    fn get_interfaces(&self) -> Vec<String> {
+        crate::Container::get_interfaces(self)
+    }
+
+    fn get_ips(
+        &self,
+        interface: Option<&str>,
+        family: Option<&str>,
+        scope: std::os::raw::c_int,
+    ) -> crate::Result<Vec<std::net::IpAddr>> {
+        crate::Container::get_ips(self, interface, family, scope)
+    }
+
+    fn attach_run_wait(
+        &self,
+        options: &mut crate::attach::Options,
+        program: &str,
+        argv: &[&str],
+    ) -> crate::Result<i32> {
+        crate::Container::attach_run_wait(self, options, program, argv)
+    }
+}
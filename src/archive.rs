@@ -0,0 +1,616 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::{BufRead, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Component, Path, PathBuf};
+
+/**
+ * Compression of an exported container.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Compression {
+    /** Plain tar. */
+    None,
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+impl crate::Container {
+    /**
+     * Write the container config and root filesystem as a tar archive.
+     *
+     * Ownership, permissions, extended attributes and device nodes are
+     * preserved. Only directory backed containers can be exported, and the
+     * container must be stopped.
+     */
+    pub fn export(&self, writer: impl Write, compression: Compression) -> crate::Result {
+        if self.is_running() {
+            return Err(crate::Error {
+                num: -libc::EBUSY,
+                str: "Unable to export a running container".to_string(),
+            });
+        }
+
+        let config = self.config_file_name().ok_or_else(|| crate::Error {
+            num: -libc::ENOENT,
+            str: "Container has no config file".to_string(),
+        })?;
+        let rootfs = self.rootfs_dir()?;
+
+        match compression {
+            Compression::None => {
+                write_archive(writer, &config, &rootfs)?;
+            }
+            Compression::Gzip => {
+                let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+                write_archive(encoder, &config, &rootfs)?.finish()?;
+            }
+            Compression::Zstd => {
+                let encoder = zstd::Encoder::new(writer, 0)?;
+                write_archive(encoder, &config, &rootfs)?.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rootfs_dir(&self) -> crate::Result<PathBuf> {
+        let rootfs = match self.get_config_item("lxc.rootfs.path") {
+            Ok(Some(rootfs)) => rootfs,
+            _ => self.get_config_item("lxc.rootfs")?.unwrap_or_default(),
+        };
+
+        let path = rootfs.strip_prefix("dir:").unwrap_or(&rootfs);
+
+        if path.starts_with('/') {
+            Ok(path.into())
+        } else {
+            Err(crate::Error {
+                num: crate::Error::UNSUPPORTED,
+                str: format!("Unable to export rootfs {rootfs}"),
+            })
+        }
+    }
+}
+
+fn write_archive<W: Write>(writer: W, config: &Path, rootfs: &Path) -> crate::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+
+    builder.append_path_with_name(config, "config")?;
+
+    let mut paths = vec![rootfs.to_path_buf()];
+    let mut links = HashMap::<_, PathBuf>::new();
+
+    while let Some(path) = paths.pop() {
+        let metadata = std::fs::symlink_metadata(&path)?;
+        let name = Path::new("rootfs").join(path.strip_prefix(rootfs).unwrap_or(&path));
+
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(&path)? {
+                paths.push(entry?.path());
+            }
+        } else if metadata.nlink() > 1 {
+            // Other names of an inode are archived as hard links to the first one
+            match links.entry((metadata.dev(), metadata.ino())) {
+                Entry::Occupied(target) => {
+                    append_hard_link(&mut builder, &name, target.get(), &metadata)?;
+                    continue;
+                }
+                Entry::Vacant(vacant) => {
+                    vacant.insert(name.clone());
+                }
+            }
+        }
+
+        append_entry(&mut builder, &path, &name, &metadata)?;
+    }
+
+    Ok(builder.into_inner()?)
+}
+
+fn append_entry<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &Path,
+    name: &Path,
+    metadata: &std::fs::Metadata,
+) -> crate::Result {
+    let file_type = metadata.file_type();
+
+    if file_type.is_socket() {
+        return Ok(());
+    }
+
+    append_xattrs(builder, path)?;
+
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+
+    if file_type.is_symlink() {
+        header.set_size(0);
+        builder.append_link(&mut header, name, std::fs::read_link(path)?)?;
+    } else if file_type.is_file() {
+        builder.append_data(&mut header, name, std::fs::File::open(path)?)?;
+    } else {
+        if file_type.is_char_device() || file_type.is_block_device() {
+            let rdev = metadata.rdev();
+
+            header.set_entry_type(if file_type.is_char_device() {
+                tar::EntryType::Char
+            } else {
+                tar::EntryType::Block
+            });
+            header.set_device_major(libc::major(rdev))?;
+            header.set_device_minor(libc::minor(rdev))?;
+        } else if file_type.is_fifo() {
+            header.set_entry_type(tar::EntryType::Fifo);
+        }
+
+        header.set_size(0);
+        builder.append_data(&mut header, name, std::io::empty())?;
+    }
+
+    Ok(())
+}
+
+fn append_hard_link<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &Path,
+    target: &Path,
+    metadata: &std::fs::Metadata,
+) -> crate::Result {
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+    header.set_entry_type(tar::EntryType::Link);
+    header.set_size(0);
+    builder.append_link(&mut header, name, target)?;
+
+    Ok(())
+}
+
+/**
+ * Write the extended attributes of `path` as a PAX header for the next entry.
+ */
+fn append_xattrs<W: Write>(builder: &mut tar::Builder<W>, path: &Path) -> crate::Result {
+    let mut records = Vec::new();
+
+    for key in list_xattrs(path)? {
+        let Some(value) = get_xattr(path, &key)? else {
+            continue;
+        };
+
+        let mut record = b" SCHILY.xattr.".to_vec();
+        record.extend_from_slice(&key);
+        record.push(b'=');
+        record.extend_from_slice(&value);
+        record.push(b'\n');
+
+        // The record length includes its own decimal representation.
+        let mut len = record.len();
+        while len != record.len() + len.to_string().len() {
+            len = record.len() + len.to_string().len();
+        }
+
+        records.extend_from_slice(len.to_string().as_bytes());
+        records.extend_from_slice(&record);
+    }
+
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let mut header = tar::Header::new_ustar();
+    header.set_entry_type(tar::EntryType::XHeader);
+    header.set_mode(0o644);
+    header.set_size(records.len() as u64);
+    builder.append_data(&mut header, "././@PaxHeader", records.as_slice())?;
+
+    Ok(())
+}
+
+fn list_xattrs(path: &Path) -> crate::Result<Vec<Vec<u8>>> {
+    let path = crate::ffi::to_cstr(path)?;
+
+    let size = unsafe { libc::llistxattr(path.as_ptr(), std::ptr::null_mut(), 0) };
+    if size < 0 {
+        return xattr_error(Vec::new());
+    }
+
+    let mut buffer = vec![0u8; size as usize];
+    let size = unsafe { libc::llistxattr(path.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len()) };
+    if size < 0 {
+        return xattr_error(Vec::new());
+    }
+    buffer.truncate(size as usize);
+
+    Ok(buffer
+        .split(|b| *b == 0)
+        .filter(|key| !key.is_empty())
+        .map(<[u8]>::to_vec)
+        .collect())
+}
+
+fn get_xattr(path: &Path, key: &[u8]) -> crate::Result<Option<Vec<u8>>> {
+    let path = crate::ffi::to_cstr(path)?;
+    let key = std::ffi::CString::new(key)?;
+
+    let size = unsafe { libc::lgetxattr(path.as_ptr(), key.as_ptr(), std::ptr::null_mut(), 0) };
+    if size < 0 {
+        return xattr_error(None);
+    }
+
+    let mut buffer = vec![0u8; size as usize];
+    let size = unsafe {
+        libc::lgetxattr(
+            path.as_ptr(),
+            key.as_ptr(),
+            buffer.as_mut_ptr().cast(),
+            buffer.len(),
+        )
+    };
+    if size < 0 {
+        return xattr_error(None);
+    }
+    buffer.truncate(size as usize);
+
+    Ok(Some(buffer))
+}
+
+/**
+ * Ignore filesystems without extended attributes support.
+ */
+fn xattr_error<T>(default: T) -> crate::Result<T> {
+    let err = std::io::Error::last_os_error();
+
+    match err.raw_os_error() {
+        Some(libc::ENOTSUP | libc::ENODATA) => Ok(default),
+        _ => Err(err.into()),
+    }
+}
+
+/**
+ * Create the container `name` in `lxcpath` from an archive written by
+ * [`crate::Container::export()`].
+ *
+ * The compression is detected. `lxc.rootfs.path`, `lxc.uts.name` and paths
+ * under the original container directory are rewritten in the config. The
+ * container directory is removed if the import fails.
+ */
+pub fn import(
+    reader: impl Read,
+    name: &str,
+    lxcpath: impl AsRef<Path>,
+) -> crate::Result<crate::Container> {
+    let lxcpath = lxcpath.as_ref();
+    let dir = lxcpath.join(name);
+
+    if dir.exists() {
+        return Err(crate::Error {
+            num: -libc::EEXIST,
+            str: format!("Container {name} already exists"),
+        });
+    }
+
+    let mut reader = std::io::BufReader::new(reader);
+    let magic = reader.fill_buf()?.to_vec();
+
+    std::fs::create_dir_all(&dir)?;
+
+    let result = extract(reader, &magic, &dir, name)
+        .and_then(|()| crate::Container::open(name, Some(lxcpath)));
+
+    if result.is_err() {
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    result
+}
+
+fn extract(reader: impl BufRead, magic: &[u8], dir: &Path, name: &str) -> crate::Result {
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        unpack(flate2::read::GzDecoder::new(reader), dir)?;
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        unpack(zstd::Decoder::with_buffer(reader)?, dir)?;
+    } else {
+        unpack(reader, dir)?;
+    }
+
+    rewrite_config(dir, name)
+}
+
+fn unpack(reader: impl Read, dir: &Path) -> crate::Result {
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_ownerships(true);
+    archive.set_preserve_mtime(true);
+    archive.set_unpack_xattrs(true);
+
+    // Like `tar::Archive::unpack()`, directories are created when needed and
+    // get their mode last, so read only ones don't prevent filling them
+    let mut directories = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+
+        if entry_type.is_dir() {
+            directories.push(entry);
+        } else if entry_type.is_character_special()
+            || entry_type.is_block_special()
+            || entry_type.is_fifo()
+        {
+            make_node(&entry, dir)?;
+        } else {
+            entry.unpack_in(dir)?;
+        }
+    }
+
+    directories.sort_by(|a, b| b.path_bytes().cmp(&a.path_bytes()));
+
+    for mut directory in directories {
+        directory.unpack_in(dir)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * `tar` unpacks device nodes and fifos as regular files.
+ */
+fn make_node<R: Read>(entry: &tar::Entry<R>, dir: &Path) -> crate::Result {
+    let header = entry.header();
+    let path = entry.path()?;
+
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(crate::Error {
+            num: crate::Error::INVALID,
+            str: format!("Invalid path in archive: {}", path.display()),
+        });
+    }
+
+    let Some(file_name) = path.file_name() else {
+        return Err(crate::Error {
+            num: crate::Error::INVALID,
+            str: format!("Invalid path in archive: {}", path.display()),
+        });
+    };
+
+    let path = create_parents(dir, &path)?.join(file_name);
+    let mode = header.mode()? & 0o7777;
+    let entry_type = header.entry_type();
+
+    let (kind, rdev) = if entry_type.is_fifo() {
+        (libc::S_IFIFO, 0)
+    } else {
+        let major = header.device_major()?.unwrap_or_default();
+        let minor = header.device_minor()?.unwrap_or_default();
+        let kind = if entry_type.is_character_special() {
+            libc::S_IFCHR
+        } else {
+            libc::S_IFBLK
+        };
+
+        (kind, libc::makedev(major, minor))
+    };
+
+    let c_path = crate::ffi::to_cstr(&path)?;
+
+    if unsafe { libc::mknod(c_path.as_ptr(), kind | mode, rdev) } < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    std::os::unix::fs::lchown(
+        &path,
+        Some(header.uid()? as u32),
+        Some(header.gid()? as u32),
+    )?;
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(mode))?;
+
+    Ok(())
+}
+
+/**
+ * Create the missing parents of `path` in `root`, failing if one resolves
+ * outside of it through a symbolic link. Returns the resolved parent.
+ */
+fn create_parents(root: &Path, path: &Path) -> crate::Result<PathBuf> {
+    let root = root.canonicalize()?;
+    let mut parent = root.clone();
+
+    for component in path.parent().into_iter().flat_map(Path::components) {
+        parent.push(component);
+
+        if std::fs::symlink_metadata(&parent).is_err() {
+            std::fs::create_dir(&parent)?;
+        }
+
+        parent = parent.canonicalize()?;
+
+        if !parent.starts_with(&root) {
+            return Err(crate::Error {
+                num: crate::Error::INVALID,
+                str: format!("{} is outside of {}", path.display(), root.display()),
+            });
+        }
+    }
+
+    Ok(parent)
+}
+
+fn rewrite_config(dir: &Path, name: &str) -> crate::Result {
+    let path = dir.join("config");
+    let config = std::fs::read(&path)?;
+    let config = String::from_utf8_lossy(&config);
+
+    let rootfs = dir.join("rootfs");
+    let rootfs = rootfs.to_string_lossy();
+
+    let old_dir = config.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+
+        if !matches!(key.trim(), "lxc.rootfs.path" | "lxc.rootfs") {
+            return None;
+        }
+
+        let value = value.trim();
+        let value = Path::new(value.strip_prefix("dir:").unwrap_or(value));
+
+        if value.file_name()?.as_bytes() == b"rootfs" {
+            value
+                .parent()
+                .map(|parent| parent.to_string_lossy().into_owned())
+        } else {
+            None
+        }
+    });
+
+    let new_dir = dir.to_string_lossy();
+    let mut rewritten = String::new();
+
+    for line in config.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            rewritten.push_str(line);
+            rewritten.push('\n');
+            continue;
+        };
+
+        let key = key.trim();
+
+        if key.starts_with('#') {
+            rewritten.push_str(line);
+        } else {
+            let value = match key {
+                "lxc.rootfs.path" => format!("dir:{rootfs}"),
+                "lxc.rootfs" => rootfs.to_string(),
+                "lxc.uts.name" | "lxc.utsname" => name.to_string(),
+                _ => match &old_dir {
+                    Some(old_dir) if value.trim() == old_dir => new_dir.to_string(),
+                    Some(old_dir) => value
+                        .trim()
+                        .replace(&format!("{old_dir}/"), &format!("{new_dir}/")),
+                    None => value.trim().to_string(),
+                },
+            };
+
+            rewritten.push_str(&format!("{key} = {value}"));
+        }
+
+        rewritten.push('\n');
+    }
+
+    std::fs::write(path, rewritten)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lxc-archive-{}-{name}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn fifo(path: &Path) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Fifo);
+        header.set_path(path).unwrap();
+        header.set_mode(0o600);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_size(0);
+        header.set_cksum();
+
+        header
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = temp_dir("source");
+        let rootfs = source.join("rootfs");
+
+        std::fs::create_dir_all(rootfs.join("etc")).unwrap();
+        std::fs::write(source.join("config"), "lxc.uts.name = source\n").unwrap();
+        std::fs::write(rootfs.join("etc/hostname"), "source\n").unwrap();
+        std::fs::hard_link(rootfs.join("etc/hostname"), rootfs.join("hostname")).unwrap();
+        std::fs::set_permissions(rootfs.join("etc"), PermissionsExt::from_mode(0o555)).unwrap();
+
+        let archive = super::write_archive(Vec::new(), &source.join("config"), &rootfs).unwrap();
+
+        let links = tar::Archive::new(archive.as_slice())
+            .entries()
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().header().entry_type().is_hard_link())
+            .count();
+        assert_eq!(links, 1);
+
+        let target = temp_dir("target");
+        super::unpack(archive.as_slice(), &target).unwrap();
+
+        let a = std::fs::metadata(target.join("rootfs/etc/hostname")).unwrap();
+        let b = std::fs::metadata(target.join("rootfs/hostname")).unwrap();
+        assert_eq!(a.ino(), b.ino());
+        assert_eq!(
+            std::fs::read_to_string(target.join("rootfs/hostname")).unwrap(),
+            "source\n"
+        );
+
+        let etc = std::fs::metadata(target.join("rootfs/etc")).unwrap();
+        assert_eq!(etc.mode() & 0o7777, 0o555);
+
+        for dir in [&source, &target] {
+            std::fs::set_permissions(dir.join("rootfs/etc"), PermissionsExt::from_mode(0o755)).ok();
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn node_outside() {
+        let outside = temp_dir("outside");
+        let target = temp_dir("escape");
+        std::os::unix::fs::symlink(&outside, target.join("dev")).unwrap();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let header = fifo(Path::new("dev/fifo"));
+        builder.append(&header, std::io::empty()).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let err = super::unpack(archive.as_slice(), &target).unwrap_err();
+
+        assert_eq!(err.num, crate::Error::INVALID);
+        assert!(!outside.join("fifo").exists());
+
+        std::fs::remove_dir_all(&outside).unwrap();
+        std::fs::remove_dir_all(&target).unwrap();
+    }
+
+    #[test]
+    fn node_parents() {
+        let target = temp_dir("parents");
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let header = fifo(Path::new("rootfs/run/fifo"));
+        builder.append(&header, std::io::empty()).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        super::unpack(archive.as_slice(), &target).unwrap();
+
+        assert!(
+            std::fs::symlink_metadata(target.join("rootfs/run/fifo"))
+                .unwrap()
+                .file_type()
+                .is_fifo()
+        );
+
+        std::fs::remove_dir_all(&target).unwrap();
+    }
+}
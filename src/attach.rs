@@ -41,3 +41,33 @@ pub fn run_command(payload: &mut std::os::raw::c_void) -> crate::Result<i32> {
 pub fn run_shell(_payload: &mut std::os::raw::c_void) -> i32 {
     unsafe { lxc_sys::lxc_attach_run_shell(_payload) }
 }
+
+/**
+ * Options keeping the environment, with the standard streams and the log
+ * redirected to `fd`.
+ */
+pub(crate) fn options(attach_flags: i32, fd: std::os::fd::RawFd) -> Options {
+    Options {
+        attach_flags,
+        env_policy: lxc_sys::lxc_attach_env_policy_t_LXC_ATTACH_KEEP_ENV,
+        extra_env_vars: std::ptr::null_mut(),
+        gid: 0,
+        uid: 0,
+        extra_keep_env: std::ptr::null_mut(),
+        initial_cwd: std::ptr::null_mut(),
+        #[cfg(feature = "v3_0")]
+        log_fd: fd,
+        stdout_fd: fd,
+        stderr_fd: fd,
+        stdin_fd: fd,
+        namespaces: -1,
+        personality: -1,
+        #[cfg(feature = "v5_0")]
+        groups: lxc_sys::lxc_groups_t {
+            size: 0,
+            list: std::ptr::null_mut(),
+        },
+        #[cfg(feature = "v5_0")]
+        lsm_label: std::ptr::null_mut(),
+    }
+}
@@ -0,0 +1,245 @@
+use crate::containers::{Outcome, StartSettings};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/**
+ * Container selected by [`plan()`].
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    /** `lxc.start.order`. */
+    pub order: i32,
+    /** `lxc.start.delay`, waited after booting the container. */
+    pub delay: Duration,
+    /** `lxc.group`. */
+    pub groups: Vec<String>,
+}
+
+/**
+ * Containers to autostart, in boot order.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Plan {
+    pub lxcpath: PathBuf,
+    pub entries: Vec<Entry>,
+    /** Containers whose config couldn't be read, left out of the plan. */
+    pub skipped: Vec<Outcome>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    /** Start the containers in boot order, like `lxc-autostart`. */
+    Boot,
+    /** Shutdown the containers in reverse order, like `lxc-autostart -s`. */
+    Shutdown(Duration),
+    /** Stop the containers in reverse order, like `lxc-autostart -k`. */
+    Kill,
+}
+
+/**
+ * Select the containers of `lxcpath` with `lxc.start.auto` set.
+ *
+ * Only containers of `groups` are selected, group by group. An empty group
+ * selects containers without group, which is the default when `groups` is
+ * empty. Within a group, containers are sorted by decreasing
+ * `lxc.start.order`, then by name. Containers whose config can't be read
+ * are reported in `skipped`.
+ */
+pub fn plan<S: AsRef<str>>(lxcpath: impl AsRef<Path>, groups: &[S]) -> crate::Result<Plan> {
+    let lxcpath = lxcpath.as_ref();
+    let groups = if groups.is_empty() {
+        vec![String::new()]
+    } else {
+        groups.iter().map(|x| x.as_ref().to_string()).collect()
+    };
+
+    let settings = crate::containers::defined(lxcpath)?
+        .into_iter()
+        .map(|name| {
+            let settings = crate::Container::open(&name, Some(lxcpath))
+                .and_then(|container| StartSettings::load(&container));
+
+            (name, settings)
+        });
+
+    let (entries, skipped) = select(settings, &groups);
+
+    Ok(Plan {
+        lxcpath: lxcpath.to_path_buf(),
+        entries,
+        skipped,
+    })
+}
+
+/**
+ * Select and sort the containers to autostart from their settings, see
+ * [`plan()`].
+ */
+fn select(
+    settings: impl IntoIterator<Item = (String, crate::Result<StartSettings>)>,
+    groups: &[String],
+) -> (Vec<Entry>, Vec<Outcome>) {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (name, settings) in settings {
+        let settings = match settings {
+            Ok(settings) => settings,
+            Err(err) => {
+                skipped.push(Outcome {
+                    name,
+                    result: Err(err),
+                });
+                continue;
+            }
+        };
+
+        if !settings.auto {
+            continue;
+        }
+
+        if let Some(rank) = settings.group_rank(groups) {
+            entries.push((
+                rank,
+                Entry {
+                    name,
+                    order: settings.order,
+                    delay: settings.delay,
+                    groups: settings.groups,
+                },
+            ));
+        }
+    }
+
+    entries.sort_by(|(a_rank, a), (b_rank, b)| {
+        a_rank
+            .cmp(b_rank)
+            .then(b.order.cmp(&a.order))
+            .then(a.name.cmp(&b.name))
+    });
+
+    (
+        entries.into_iter().map(|(_, entry)| entry).collect(),
+        skipped,
+    )
+}
+
+/**
+ * Run `action` on the containers of `plan`, one at a time.
+ *
+ * Containers already in the wanted state are left untouched.
+ */
+pub fn execute(plan: &Plan, action: Action) -> Vec<Outcome> {
+    let entries: Box<dyn Iterator<Item = &Entry>> = match action {
+        Action::Boot => Box::new(plan.entries.iter()),
+        Action::Shutdown(_) | Action::Kill => Box::new(plan.entries.iter().rev()),
+    };
+
+    let mut outcomes = Vec::new();
+
+    for entry in entries {
+        let result = crate::Container::open(&entry.name, Some(&plan.lxcpath))
+            .and_then(|container| run(&container, entry, action));
+
+        outcomes.push(Outcome {
+            name: entry.name.clone(),
+            result,
+        });
+    }
+
+    outcomes
+}
+
+fn run(container: &crate::Container, entry: &Entry, action: Action) -> crate::Result {
+    match action {
+        Action::Boot => {
+            if container.is_running() {
+                return Ok(());
+            }
+
+            container.start(false, &[])?;
+
+            if !entry.delay.is_zero() {
+                std::thread::sleep(entry.delay);
+            }
+
+            Ok(())
+        }
+        Action::Shutdown(timeout) if container.is_running() => {
+            container.shutdown(i32::try_from(timeout.as_secs()).unwrap_or(i32::MAX))
+        }
+        Action::Kill if container.is_running() => container.stop(),
+        Action::Shutdown(_) | Action::Kill => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::containers::StartSettings;
+
+    fn settings(
+        name: &str,
+        auto: bool,
+        order: i32,
+        groups: &[&str],
+    ) -> (String, crate::Result<StartSettings>) {
+        (
+            name.to_string(),
+            Ok(StartSettings {
+                auto,
+                order,
+                groups: groups.iter().map(ToString::to_string).collect(),
+                ..StartSettings::default()
+            }),
+        )
+    }
+
+    fn select(groups: &[&str]) -> (Vec<String>, Vec<String>) {
+        let containers = vec![
+            settings("web2", true, 10, &["web"]),
+            settings("db", true, 20, &["infra"]),
+            settings("web1", true, 10, &["web", "infra"]),
+            settings("tools", true, 0, &[]),
+            settings("manual", false, 50, &[]),
+            (
+                "broken".to_string(),
+                Err(crate::Error {
+                    num: -1,
+                    str: "unreadable".to_string(),
+                }),
+            ),
+            settings("cache", true, 30, &[]),
+        ];
+        let groups = groups.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let (entries, skipped) = super::select(containers, &groups);
+
+        (
+            entries.into_iter().map(|x| x.name).collect(),
+            skipped.into_iter().map(|x| x.name).collect(),
+        )
+    }
+
+    #[test]
+    fn ungrouped() {
+        assert_eq!(
+            select(&[""]),
+            (
+                vec!["cache".to_string(), "tools".to_string()],
+                vec!["broken".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let (entries, _) = select(&["web", "infra"]);
+
+        assert_eq!(entries, ["web1", "web2", "db"]);
+
+        let (entries, _) = select(&["infra", "", "web"]);
+
+        assert_eq!(entries, ["db", "web1", "cache", "tools", "web2"]);
+    }
+}
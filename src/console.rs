@@ -1,2 +1,65 @@
 #[cfg(feature = "v3_0")]
 pub use lxc_sys::lxc_console_log as Log;
+
+/**
+ * Pseudo terminal allocated in a container, see `Container::open_pty()`.
+ */
+#[cfg(feature = "v5_0")]
+#[derive(Debug)]
+pub struct Pty {
+    /** Controlling side, kept by the caller. */
+    pub ptx: std::os::fd::OwnedFd,
+    /** Terminal side, given to the process running in the container. */
+    pub pts: std::os::fd::OwnedFd,
+    /** Number of the terminal in the container's `/dev/pts`. */
+    pub index: u32,
+}
+
+#[cfg(feature = "v5_0")]
+impl Pty {
+    pub(crate) fn open(devpts: &impl std::os::fd::AsFd) -> crate::Result<Self> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        let check = |ret: libc::c_int| {
+            if ret < 0 {
+                Err(std::io::Error::last_os_error())
+            } else {
+                Ok(ret)
+            }
+        };
+
+        let ptx = check(unsafe {
+            libc::openat(
+                devpts.as_fd().as_raw_fd(),
+                c"ptmx".as_ptr(),
+                libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+            )
+        })?;
+        let ptx = unsafe { OwnedFd::from_raw_fd(ptx) };
+
+        let unlock: libc::c_int = 0;
+        check(unsafe { libc::ioctl(ptx.as_raw_fd(), libc::TIOCSPTLCK, &unlock) })?;
+
+        let mut index: libc::c_uint = 0;
+        check(unsafe { libc::ioctl(ptx.as_raw_fd(), libc::TIOCGPTN, &mut index) })?;
+
+        let pts = check(unsafe {
+            libc::ioctl(
+                ptx.as_raw_fd(),
+                libc::TIOCGPTPEER,
+                libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+            )
+        })?;
+        let pts = unsafe { OwnedFd::from_raw_fd(pts) };
+
+        Ok(Self { ptx, pts, index })
+    }
+
+    /**
+     * Path of the terminal in the container.
+     */
+    #[must_use]
+    pub fn path(&self) -> std::path::PathBuf {
+        format!("/dev/pts/{}", self.index).into()
+    }
+}
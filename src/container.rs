@@ -1,7 +1,13 @@
-use crate::ffi::to_cstr;
 #[cfg(feature = "v1_1")]
 use crate::ffi::to_mut_cstr;
-use std::ptr::{null, null_mut};
+use crate::ffi::{opt_ptr, to_cstr, to_cstr_opt, to_cstr_vec, to_nta};
+use std::ffi::OsString;
+#[cfg(feature = "v3_2")]
+use std::os::fd::OwnedFd;
+use std::os::raw::{c_char, c_int};
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
 
 macro_rules! get {
     ( $container:ident . $prop:ident ) => {{ unsafe { (*$container.inner).$prop } }};
@@ -9,13 +15,11 @@ macro_rules! get {
     ( $container:ident . $prop:ident -> c_str ) => {{
         let result = get!($container.$prop);
 
-        let str = if result.is_null() {
-            ""
+        if result.is_null() {
+            std::ffi::OsString::new()
         } else {
-            unsafe { std::ffi::CStr::from_ptr(result).to_str().unwrap() }
-        };
-
-        str.to_string()
+            crate::ffi::to_os_string(result)
+        }
     }};
 }
 
@@ -28,20 +32,18 @@ macro_rules! call {
         if result.is_null() {
             Err($container.last_error())
         } else {
-            let vec = crate::ffi::vec_from_nta(result);
-
-            let vec = vec.iter()
+            let vec = crate::ffi::vec_from_nta(result)
+                .into_iter()
                 .map(|e| {
-                    let str = unsafe {
-                        std::ffi::CStr::from_ptr(*e)
-                    };
+                    let str = crate::ffi::to_os_string(e);
+                    unsafe { lxc_sys::free(e as *mut std::ffi::c_void) };
 
-                    str.to_str()
-                        .unwrap()
-                        .to_string()
+                    str
                 })
                 .collect::<Vec<_>>();
 
+            unsafe { lxc_sys::free(result as *mut std::ffi::c_void) };
+
             Ok(vec)
         }
     }};
@@ -57,13 +59,26 @@ macro_rules! call {
             (*$container.inner).$method.unwrap()($container.inner, $($arg,)*)
         };
 
-        let str = unsafe {
-            std::ffi::CStr::from_ptr(result)
+        if result.is_null() {
+            None
+        } else {
+            Some(crate::ffi::to_os_string(result))
+        }
+    }};
+
+    ( $container:ident . $method:ident( $( $arg:expr ),* ) -> owned c_str ) => {{
+        let result = unsafe {
+            (*$container.inner).$method.unwrap()($container.inner, $($arg,)*)
         };
 
-        str.to_str()
-            .unwrap()
-            .to_string()
+        if result.is_null() {
+            None
+        } else {
+            let str = crate::ffi::to_os_string(result);
+            unsafe { lxc_sys::free(result as *mut std::ffi::c_void) };
+
+            Some(str)
+        }
     }};
 
     ( $container:ident . $method:ident( $( $arg:expr ),* ) -> bool ) => {{
@@ -99,19 +114,28 @@ impl Container {
     /**
      * Create a new container.
      */
-    pub fn new(
-        name: &str,
-        config_path: Option<&std::path::Path>,
-    ) -> std::result::Result<Self, String> {
-        let config_path = match config_path {
-            Some(path) => cstr!(path.to_str().unwrap()),
-            None => null(),
-        };
+    pub fn new(name: &str, config_path: Option<&Path>) -> std::result::Result<Self, String> {
+        Self::open(name, config_path).map_err(|err| err.str)
+    }
+
+    /**
+     * Create a new container, like `new()` but failing with a
+     * [`crate::Error`].
+     */
+    pub fn open(name: &str, config_path: Option<&Path>) -> crate::Result<Self> {
+        crate::load()?;
+
+        let c_name = to_cstr(name)?;
+        let config_path = to_cstr_opt(config_path)?;
 
-        let inner = unsafe { lxc_sys::lxc_container_new(cstr!(name), config_path) };
+        let inner =
+            unsafe { lxc_sys::lxc_container_new(c_name.as_ptr(), opt_ptr(config_path.as_deref())) };
 
         if inner.is_null() {
-            Err(format!("Unable to create container {name}"))
+            Err(crate::Error {
+                num: crate::Error::INVALID,
+                str: format!("Unable to create container {name}"),
+            })
         } else {
             Ok(Self { inner })
         }
@@ -136,6 +160,8 @@ impl Container {
     #[must_use]
     pub fn error_string(&self) -> String {
         get!(self.error_string -> c_str)
+            .to_string_lossy()
+            .into_owned()
     }
 
     /**
@@ -158,8 +184,8 @@ impl Container {
      * Full path to configuration file.
      */
     #[must_use]
-    pub fn config_path(&self) -> String {
-        get!(self.config_path -> c_str)
+    pub fn config_path(&self) -> PathBuf {
+        get!(self.config_path -> c_str).into()
     }
 
     /**
@@ -171,11 +197,13 @@ impl Container {
     }
 
     /**
-     * Wait for container to reach a particular state.
+     * Determine the current state of the container.
+     *
+     * Returns `None` if liblxc can't tell.
      */
     #[must_use]
-    pub fn state(&self) -> String {
-        call!(self.state() -> c_str)
+    pub fn state(&self) -> Option<String> {
+        call!(self.state() -> c_str).map(|state| state.to_string_lossy().into_owned())
     }
 
     /**
@@ -211,18 +239,18 @@ impl Container {
     /**
      * Load the specified configuration for the container.
      */
-    pub fn load_config(&self, alt_file: &str) -> crate::Result {
-        call!(self.load_config(cstr!(alt_file)) -> bool)
+    pub fn load_config(&self, alt_file: impl AsRef<Path>) -> crate::Result {
+        call!(self.load_config(cstr!(alt_file.as_ref())) -> bool)
     }
 
     /**
      * Start the container.
      */
     pub fn start(&self, use_init: bool, argv: &[&str]) -> crate::Result {
-        let mut argv: Vec<*mut i8> = argv.iter().map(|e| to_cstr(e).into_raw()).collect();
-        argv.push(null_mut());
+        let argv = to_cstr_vec(argv)?;
+        let argv = to_nta(&argv);
 
-        call!(self.start(use_init as i32, argv.as_mut_ptr()) -> bool)
+        call!(self.start(use_init as i32, argv.as_ptr() as *const *mut _) -> bool)
     }
 
     /**
@@ -252,8 +280,8 @@ impl Container {
      * Return current config file name.
      */
     #[must_use]
-    pub fn config_file_name(&self) -> String {
-        call!(self.config_file_name() -> c_str)
+    pub fn config_file_name(&self) -> Option<PathBuf> {
+        call!(self.config_file_name() -> owned c_str).map(PathBuf::from)
     }
 
     /**
@@ -280,8 +308,8 @@ impl Container {
     /**
      * Save configuaration to a file.
      */
-    pub fn save_config(&self, alt_file: &str) -> crate::Result {
-        call!(self.save_config(cstr!(alt_file)) -> bool)
+    pub fn save_config(&self, alt_file: impl AsRef<Path>) -> crate::Result {
+        call!(self.save_config(cstr!(alt_file.as_ref())) -> bool)
     }
 
     /**
@@ -300,22 +328,45 @@ impl Container {
             None => null_mut(),
         };
 
-        let mut argv: Vec<*mut i8> = argv.iter().map(|e| to_cstr(e).into_raw()).collect();
-        argv.push(null_mut());
+        let template = to_cstr_opt(template)?;
+        let bdevtype = to_cstr_opt(bdevtype)?;
+        let argv = to_cstr_vec(argv)?;
+        let argv = to_nta(&argv);
 
         call!(
             self.create(
-                match template {
-                    Some(value) => cstr!(value),
-                    None => null(),
-                },
-                match bdevtype {
-                    Some(value) => cstr!(value),
-                    None => null(),
-                },
+                opt_ptr(template.as_deref()),
+                opt_ptr(bdevtype.as_deref()),
                 specs,
                 flags.bits(),
-                argv.as_ptr()
+                argv.as_ptr() as *const *mut _
+            ) -> bool
+        )
+    }
+
+    /**
+     * Create a container from options.
+     */
+    pub fn create_with(&self, options: &crate::CreateOptions) -> crate::Result {
+        let template = options.template_name()?;
+        let bdevtype = to_cstr_opt(options.bdevtype())?;
+        let mut specs = options.specs().transpose()?;
+        let specs = match specs.as_mut() {
+            Some(specs) => &mut specs.inner,
+            None => null_mut(),
+        };
+        let argv = options.argv();
+        let argv = argv.iter().map(String::as_str).collect::<Vec<_>>();
+        let argv = to_cstr_vec(&argv)?;
+        let argv = to_nta(&argv);
+
+        call!(
+            self.create(
+                opt_ptr(template.as_deref()),
+                opt_ptr(bdevtype.as_deref()),
+                specs,
+                options.flags().bits(),
+                argv.as_ptr() as *const *mut _
             ) -> bool
         )
     }
@@ -358,76 +409,107 @@ impl Container {
     /**
      * Retrieve the value of a config item.
      */
-    #[must_use]
-    pub fn get_config_item(&self, key: &str) -> Option<String> {
-        let size = call!(self.get_config_item(cstr!(key), null_mut(), 0));
-        if size < 0 {
-            return None;
-        }
-        let mut retv = vec![0; size as usize];
+    pub fn get_config_item(&self, key: &str) -> crate::Result<Option<String>> {
+        self.get_config_item_os(key)?
+            .map(crate::ffi::into_string)
+            .transpose()
+    }
 
-        call!(self.get_config_item(cstr!(key), retv.as_mut_ptr() as *mut i8, size + 1));
+    /**
+     * Retrieve the value of a config item, without requiring UTF-8.
+     */
+    pub fn get_config_item_os(&self, key: &str) -> crate::Result<Option<OsString>> {
+        let key = to_cstr(key)?;
 
-        Some(String::from_utf8(retv).unwrap())
+        Ok(read_buffer(|retv, inlen| {
+            call!(self.get_config_item(key.as_ptr(), retv, inlen))
+        }))
     }
 
     /**
      * Retrieve the value of a config item from running container.
+     *
+     * Returns `None` if the container isn't running or the key is unknown.
      */
-    #[must_use]
-    pub fn get_running_config_item(&self, key: &str) -> String {
-        call!(self.get_running_config_item(cstr!(key)) -> c_str)
+    pub fn get_running_config_item(&self, key: &str) -> crate::Result<Option<String>> {
+        self.get_running_config_item_os(key)?
+            .map(crate::ffi::into_string)
+            .transpose()
+    }
+
+    /**
+     * Retrieve the value of a config item from running container, without
+     * requiring UTF-8.
+     */
+    pub fn get_running_config_item_os(&self, key: &str) -> crate::Result<Option<OsString>> {
+        Ok(call!(self.get_running_config_item(cstr!(key)) -> owned c_str))
     }
 
     /**
      * Retrieve a list of config item keys given a key prefix.
      */
-    #[must_use]
-    pub fn get_keys(&self, key: &str) -> String {
-        let size = call!(self.get_keys(cstr!(key), null_mut(), 0));
-        let mut retv = Vec::with_capacity(size as usize);
+    pub fn get_keys(&self, key: &str) -> crate::Result<String> {
+        let key = to_cstr(key)?;
 
-        call!(self.get_keys(cstr!(key), retv.as_mut_ptr() as *mut i8, size));
+        let keys = read_buffer(|retv, inlen| call!(self.get_keys(key.as_ptr(), retv, inlen)))
+            .ok_or_else(|| self.last_error())?;
 
-        String::from_utf8(retv).unwrap()
+        crate::ffi::into_string(keys)
     }
 
     /**
      * Obtain a list of network interfaces.
+     *
+     * Invalid UTF-8 sequences in interface names are replaced.
      */
     #[must_use]
     pub fn get_interfaces(&self) -> Vec<String> {
-        call!(self.get_interfaces() -> [c_str]).unwrap_or_default()
+        call!(self.get_interfaces() -> [c_str])
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.to_string_lossy().into_owned())
+            .collect()
     }
 
     /**
      * Determine the list of container IP addresses.
+     *
+     * Fails if liblxc returns an address which can't be parsed.
      */
-    #[must_use]
     pub fn get_ips(
         &self,
         interface: Option<&str>,
         family: Option<&str>,
         scope: std::os::raw::c_int,
-    ) -> Vec<std::net::IpAddr> {
-        call!(self.get_ips(interface.map_or(null(), |x| cstr!(x)), family.map_or(null(), |x| cstr!(x)), scope) -> [c_str])
+    ) -> crate::Result<Vec<std::net::IpAddr>> {
+        let interface = to_cstr_opt(interface)?;
+        let family = to_cstr_opt(family)?;
+
+        call!(self.get_ips(opt_ptr(interface.as_deref()), opt_ptr(family.as_deref()), scope) -> [c_str])
             .unwrap_or_default()
-            .iter()
-            .map(|x| x.parse().unwrap())
+            .into_iter()
+            .map(|x| {
+                let ip = crate::ffi::into_string(x)?;
+
+                ip.parse().map_err(|err| crate::Error {
+                    num: crate::Error::INVALID,
+                    str: format!("invalid IP address {ip:?}: {err}"),
+                })
+            })
             .collect()
     }
 
     /**
      * Retrieve the specified cgroup subsystem value for the container.
      */
-    #[must_use]
-    pub fn get_cgroup_item(&self, subsys: &str) -> String {
-        let size = call!(self.get_cgroup_item(cstr!(subsys), null_mut(), 0));
-        let mut retv = Vec::with_capacity(size as usize);
+    pub fn get_cgroup_item(&self, subsys: &str) -> crate::Result<String> {
+        let subsys = to_cstr(subsys)?;
 
-        call!(self.get_cgroup_item(cstr!(subsys), retv.as_mut_ptr() as *mut i8, size));
+        let value =
+            read_buffer(|retv, inlen| call!(self.get_cgroup_item(subsys.as_ptr(), retv, inlen)))
+                .ok_or_else(|| self.last_error())?;
 
-        String::from_utf8(retv).unwrap()
+        crate::ffi::into_string(value)
     }
 
     /**
@@ -448,21 +530,20 @@ impl Container {
      * general at the moment.
      */
     #[must_use]
-    pub fn get_config_path(&self) -> String {
-        call!(self.get_config_path() -> c_str)
+    pub fn get_config_path(&self) -> Option<PathBuf> {
+        call!(self.get_config_path() -> c_str).map(PathBuf::from)
     }
 
     /**
      * Set the full path to the containers configuration directory.
      */
-    pub fn set_config_path(&self, path: &str) -> crate::Result {
-        call!(self.set_config_path(cstr!(path)) -> bool)
+    pub fn set_config_path(&self, path: impl AsRef<Path>) -> crate::Result {
+        call!(self.set_config_path(cstr!(path.as_ref())) -> bool)
     }
 
     /**
      * Copy a stopped container.
      */
-    #[must_use]
     #[allow(clippy::too_many_arguments)]
     pub fn clone(
         &self,
@@ -473,7 +554,7 @@ impl Container {
         bdevdata: &str,
         newsize: u64,
         hookargs: &[String],
-    ) -> Self {
+    ) -> crate::Result<Self> {
         if !hookargs.is_empty() {
             unimplemented!();
         }
@@ -488,7 +569,11 @@ impl Container {
             null_mut()
         ));
 
-        Self { inner }
+        if inner.is_null() {
+            Err(self.last_error())
+        } else {
+            Ok(Self { inner })
+        }
     }
 
     /**
@@ -541,8 +626,8 @@ impl Container {
         program: &str,
         argv: &[&str],
     ) -> crate::Result<i32> {
-        let mut argv: Vec<*const i8> = argv.iter().map(|e| cstr!(*e) as *const i8).collect();
-        argv.push(null());
+        let argv = to_cstr_vec(argv)?;
+        let argv = to_nta(&argv);
 
         let pid = call!(self.attach_run_wait(options, cstr!(program), argv.as_ptr()));
 
@@ -560,19 +645,73 @@ impl Container {
      * `/var/lib/lxc/<c>/snaps/snap<n>` where `<c>` represents the container
      * name and `<n>` represents the zero-based snapshot number.
      */
-    pub fn snapshot(&self, commentfile: &str) -> crate::Result {
-        call!(self.snapshot(cstr!(commentfile)) -> int)
+    pub fn snapshot(&self, commentfile: impl AsRef<Path>) -> crate::Result<crate::SnapshotName> {
+        let number = call!(self.snapshot(cstr!(commentfile.as_ref())));
+
+        if number < 0 {
+            Err(self.last_error())
+        } else {
+            Ok(crate::SnapshotName::from_number(number))
+        }
+    }
+
+    /**
+     * Create a container snapshot with a comment.
+     */
+    pub fn snapshot_with_comment(&self, comment: &str) -> crate::Result<crate::SnapshotName> {
+        use std::io::Write;
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let commentfile = std::env::temp_dir().join(format!(
+            "lxc-snapshot-comment-{}-{nanos}",
+            std::process::id()
+        ));
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&commentfile)?;
+        let result = file
+            .write_all(comment.as_bytes())
+            .map_err(crate::Error::from)
+            .and_then(|()| self.snapshot(&commentfile));
+
+        std::fs::remove_file(&commentfile).ok();
+
+        result
     }
 
     /**
      * Obtain a list of container snapshots.
      */
-    #[must_use]
-    pub fn snapshot_list(&self) -> Vec<crate::Snapshot> {
-        let mut list = Vec::new();
-        call!(self.snapshot_list(&mut list.as_mut_ptr()));
+    pub fn snapshot_list(&self) -> crate::Result<Vec<crate::Snapshot>> {
+        let mut raw = std::ptr::null_mut();
+        let size = call!(self.snapshot_list(&mut raw));
+
+        if size < 0 {
+            return Err(self.last_error());
+        }
+
+        let mut list = Vec::with_capacity(size as usize);
 
-        list
+        for x in 0..size as usize {
+            unsafe {
+                let snapshot = raw.add(x);
+
+                list.push(crate::Snapshot::from_raw(&*snapshot));
+
+                if let Some(free) = (*snapshot).free {
+                    free(snapshot);
+                }
+            }
+        }
+
+        unsafe { lxc_sys::free(raw as *mut std::ffi::c_void) };
+
+        Ok(list)
     }
 
     /**
@@ -585,6 +724,66 @@ impl Container {
         call!(self.snapshot_restore(cstr!(snapname), cstr!(newname)) -> bool)
     }
 
+    /**
+     * Replace the container by one of its snapshots.
+     *
+     * A running container is stopped before the restoration and started
+     * again once it succeeded.
+     *
+     * liblxc destroys the container before copying the snapshot in its
+     * place: if the restoration fails, the container may be left destroyed
+     * and is not restarted.
+     */
+    pub fn snapshot_restore_in_place(&self, snapname: &str) -> crate::Result {
+        let running = self.is_running();
+
+        if running {
+            self.stop()?;
+        }
+
+        let restore = || -> crate::Result {
+            let name = get!(self.name);
+            call!(self.snapshot_restore(cstr!(snapname), name) -> bool)?;
+
+            if let Some(config) = self.config_file_name() {
+                self.clear_config();
+                self.load_config(config)?;
+            }
+
+            Ok(())
+        };
+
+        restore()?;
+
+        if running {
+            self.start(false, &[])?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * List files added, removed or changed between two snapshots.
+     *
+     * Both snapshots must be directory backed.
+     */
+    pub fn snapshot_diff(&self, a: &str, b: &str) -> crate::Result<crate::SnapshotDiff> {
+        let snapshots = self.snapshot_list()?;
+
+        let rootfs = |name: &str| {
+            snapshots
+                .iter()
+                .find(|snapshot| snapshot.name.as_str() == name)
+                .ok_or_else(|| crate::Error {
+                    num: -libc::ENOENT,
+                    str: format!("Snapshot {name} not found"),
+                })?
+                .rootfs()
+        };
+
+        crate::SnapshotDiff::between(&rootfs(a)?, &rootfs(b)?)
+    }
+
     /**
      * Destroy the specified snapshot.
      */
@@ -604,24 +803,18 @@ impl Container {
      * Add specified device to the container.
      */
     pub fn add_device_node(&self, src_path: &str, dest_path: Option<&str>) -> crate::Result {
-        let ptr = match dest_path {
-            Some(s) => cstr!(s),
-            None => std::ptr::null(),
-        };
+        let dest_path = to_cstr_opt(dest_path)?;
 
-        call!(self.add_device_node(cstr!(src_path), ptr) -> bool)
+        call!(self.add_device_node(cstr!(src_path), opt_ptr(dest_path.as_deref())) -> bool)
     }
 
     /**
      * Remove specified device from the container.
      */
     pub fn remove_device_node(&self, src_path: &str, dest_path: Option<&str>) -> crate::Result {
-        let ptr = match dest_path {
-            Some(s) => cstr!(s),
-            None => std::ptr::null(),
-        };
+        let dest_path = to_cstr_opt(dest_path)?;
 
-        call!(self.remove_device_node(cstr!(src_path), ptr) -> bool)
+        call!(self.remove_device_node(cstr!(src_path), opt_ptr(dest_path.as_deref())) -> bool)
     }
 
     /**
@@ -644,16 +837,21 @@ impl Container {
      * Checkpoint a container.
      */
     #[cfg(feature = "v1_1")]
-    pub fn checkpoint(&self, directory: &str, stop: bool, verbose: bool) -> crate::Result {
-        call!(self.checkpoint(to_mut_cstr(directory).as_mut_ptr(), stop, verbose) -> bool)
+    pub fn checkpoint(
+        &self,
+        directory: impl AsRef<Path>,
+        stop: bool,
+        verbose: bool,
+    ) -> crate::Result {
+        call!(self.checkpoint(to_mut_cstr(directory.as_ref())?.as_mut_ptr(), stop, verbose) -> bool)
     }
 
     /**
      * Restore a container from a checkpoint.
      */
     #[cfg(feature = "v1_1")]
-    pub fn restore(&self, directory: &str, verbose: bool) -> crate::Result {
-        call!(self.restore(to_mut_cstr(directory).as_mut_ptr(), verbose) -> bool)
+    pub fn restore(&self, directory: impl AsRef<Path>, verbose: bool) -> crate::Result {
+        call!(self.restore(to_mut_cstr(directory.as_ref())?.as_mut_ptr(), verbose) -> bool)
     }
 
     /**
@@ -685,6 +883,8 @@ impl Container {
      */
     #[cfg(feature = "v3_0")]
     pub fn console_log(&self, log: &mut crate::console::Log) -> crate::Result {
+        crate::Capabilities::require(|x| x.console_log, "console_log")?;
+
         call!(self.console_log(log) -> int)
     }
 
@@ -693,63 +893,112 @@ impl Container {
      */
     #[cfg(feature = "v3_0")]
     pub fn reboot2(&self, timetout: i32) -> crate::Result {
+        crate::Capabilities::require(|x| x.reboot2, "reboot2")?;
+
         call!(self.reboot2(timetout) -> bool)
     }
 
     /**
-     * Mount the host's path `source` onto the container's path `target`.
+     * Mount a host path in the running container, until the returned guard
+     * is dropped.
+     *
+     * Flags of a bind mount, e.g. read-only, are applied by remounting it
+     * from a process attached to the container.
      */
     #[cfg(feature = "v3_1")]
-    pub fn mount(
-        &self,
-        source: &str,
-        target: &str,
-        filesystemtype: &str,
-        mountflags: u64,
-        data: &std::os::raw::c_void,
-        mnt: &mut crate::Mount,
-    ) -> crate::Result {
-        call!(self.mount(cstr!(source), cstr!(target), cstr!(filesystemtype), mountflags, data, mnt) -> int)
+    pub fn mount(&self, options: &crate::MountOptions) -> crate::Result<crate::MountGuard<'_>> {
+        crate::Capabilities::require(|x| x.mount, "mount")?;
+
+        let fstype = to_cstr_opt(options.fstype.as_deref())?;
+        let data = to_cstr_opt(options.data.as_deref())?;
+        let mut mnt = Self::mount_api();
+
+        call!(self.mount(
+            cstr!(&options.source),
+            cstr!(&options.target),
+            opt_ptr(fstype.as_deref()),
+            options.flags.bits(),
+            opt_ptr(data.as_deref()).cast(),
+            &mut mnt
+        ) -> int)?;
+
+        let guard = crate::MountGuard::new(self, &options.target);
+
+        // The kernel ignores the other flags when creating a bind mount
+        let extra = options.flags - crate::MountFlags::BIND - crate::MountFlags::REC;
+
+        if options.flags.contains(crate::MountFlags::BIND) && !extra.is_empty() {
+            self.remount(&options.target, options.flags)?;
+        }
+
+        Ok(guard)
     }
 
     /**
-     * Unmount the container's path `target`.
+     * Unmount the container's path `target`, `flags` are `umount2` ones.
      */
     #[cfg(feature = "v3_1")]
-    pub fn umount(&self, target: &str, mountflags: u64, mnt: &mut crate::Mount) -> crate::Result {
-        call!(self.umount(cstr!(target), mountflags, mnt) -> int)
+    pub fn umount(&self, target: impl AsRef<Path>, flags: u64) -> crate::Result {
+        crate::Capabilities::require(|x| x.mount, "umount")?;
+
+        let mut mnt = Self::mount_api();
+
+        call!(self.umount(cstr!(target.as_ref()), flags, &mut mnt) -> int)
     }
 
     /**
      * Retrieve a file descriptor for the container's seccomp filter.
      */
     #[cfg(feature = "v3_2")]
-    pub fn seccomp_notify_fd(&self) -> i32 {
-        call!(self.seccomp_notify_fd())
+    pub fn seccomp_notify_fd(&self) -> crate::Result<OwnedFd> {
+        crate::Capabilities::require(|x| x.seccomp_notify, "seccomp_notify_fd")?;
+
+        let fd = call!(self.seccomp_notify_fd());
+
+        if fd < 0 {
+            return Err(self.fd_error(fd));
+        }
+
+        // The fd belongs to the container, return a duplicate
+        Ok(unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?)
     }
 
     /**
      * Retrieve a pidfd for the container's init process.
      */
     #[cfg(feature = "v4_0")]
-    pub fn init_pidfd(&self) -> i32 {
-        call!(self.init_pidfd())
+    pub fn init_pidfd(&self) -> crate::Result<OwnedFd> {
+        crate::Capabilities::require(|x| x.pidfd, "init_pidfd")?;
+
+        self.owned_fd(call!(self.init_pidfd()))
     }
 
     /**
      * Retrieve a file descriptor for the running container's seccomp filter.
      */
     #[cfg(feature = "v5_0")]
-    pub fn seccomp_notify_fd_active(&self) -> i32 {
-        call!(self.seccomp_notify_fd_active())
+    pub fn seccomp_notify_fd_active(&self) -> crate::Result<OwnedFd> {
+        crate::Capabilities::require(|x| x.seccomp_notify_active, "seccomp_notify_fd_active")?;
+
+        self.owned_fd(call!(self.seccomp_notify_fd_active()))
     }
 
     /**
      * Retrieve a mount fd for the container's devpts instance.
      */
     #[cfg(feature = "v5_0")]
-    pub fn devpts_fd(&self) -> i32 {
-        call!(self.devpts_fd())
+    pub fn devpts_fd(&self) -> crate::Result<OwnedFd> {
+        crate::Capabilities::require(|x| x.devpts_fd, "devpts_fd")?;
+
+        self.owned_fd(call!(self.devpts_fd()))
+    }
+
+    /**
+     * Allocate a new pty in the container's devpts instance.
+     */
+    #[cfg(feature = "v5_0")]
+    pub fn open_pty(&self) -> crate::Result<crate::Pty> {
+        crate::Pty::open(&self.devpts_fd()?)
     }
 
     /**
@@ -757,6 +1006,8 @@ impl Container {
      */
     #[cfg(feature = "v6_0")]
     pub fn set_timeout(&self, timeout: i32) -> crate::Result {
+        crate::Capabilities::require(|x| x.set_timeout, "set_timeout")?;
+
         call!(self.set_timeout(timeout) -> bool)
     }
 
@@ -775,10 +1026,38 @@ impl Container {
         self.inner
     }
 
+    #[cfg(feature = "v3_1")]
+    fn mount_api() -> crate::Mount {
+        crate::Mount {
+            version: lxc_sys::LXC_MOUNT_API_V1 as i32,
+        }
+    }
+
+    #[cfg(feature = "v4_0")]
+    fn owned_fd(&self, fd: c_int) -> crate::Result<OwnedFd> {
+        use std::os::fd::FromRawFd;
+
+        if fd < 0 {
+            Err(self.fd_error(fd))
+        } else {
+            Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+        }
+    }
+
+    #[cfg(feature = "v3_2")]
+    fn fd_error(&self, fd: c_int) -> crate::Error {
+        // liblxc returns either -1 or a negated errno
+        if fd < -1 {
+            std::io::Error::from_raw_os_error(-fd).into()
+        } else {
+            self.last_error()
+        }
+    }
+
     fn last_error(&self) -> crate::Error {
         crate::Error {
             num: get!(self.error_num),
-            str: get!(self.error_string -> c_str),
+            str: self.error_string(),
         }
     }
 }
@@ -798,7 +1077,10 @@ impl std::fmt::Debug for Container {
                 if $inner.$field.is_null() {
                     $debug.field(stringify!($field), &Option::<&str>::None);
                 } else {
-                    $debug.field(stringify!($field), &crate::ffi::to_string($inner.$field));
+                    $debug.field(
+                        stringify!($field),
+                        &crate::ffi::to_string_lossy($inner.$field),
+                    );
                 }
             };
         }
@@ -836,3 +1118,27 @@ impl std::fmt::Debug for Container {
         debug.finish()
     }
 }
+
+/**
+ * Read a value with a liblxc getter following the `snprintf` convention.
+ *
+ * Returns `None` if the getter fails.
+ */
+fn read_buffer(getter: impl Fn(*mut c_char, c_int) -> c_int) -> Option<OsString> {
+    let size = getter(null_mut(), 0);
+
+    if size < 0 {
+        return None;
+    }
+
+    let mut retv = vec![0u8; size as usize + 1];
+    let size = getter(retv.as_mut_ptr() as *mut c_char, retv.len() as c_int);
+
+    if size < 0 {
+        return None;
+    }
+
+    retv.truncate((size as usize).min(retv.len() - 1));
+
+    Some(OsString::from_vec(retv))
+}
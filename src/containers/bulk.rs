@@ -0,0 +1,428 @@
+use crate::ContainerApi;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/**
+ * Result of a bulk operation for one container.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome<T = ()> {
+    pub name: String,
+    pub result: crate::Result<T>,
+}
+
+/**
+ * Start settings of a container, read from its config.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct StartSettings {
+    pub auto: bool,
+    pub order: i32,
+    pub delay: Duration,
+    pub groups: Vec<String>,
+}
+
+impl StartSettings {
+    pub fn load(container: &impl ContainerApi) -> crate::Result<Self> {
+        let item = |key| -> crate::Result<String> {
+            Ok(container
+                .get_config_item(key)?
+                .unwrap_or_default()
+                .trim()
+                .to_string())
+        };
+
+        Ok(Self {
+            auto: item("lxc.start.auto")? == "1",
+            order: item("lxc.start.order")?.parse().unwrap_or_default(),
+            delay: Duration::from_secs(item("lxc.start.delay")?.parse().unwrap_or_default()),
+            groups: item("lxc.group")?
+                .split([',', '\n'])
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(String::from)
+                .collect(),
+        })
+    }
+
+    /**
+     * Position of the first of `groups` the container belongs to, an empty
+     * group matching containers without group.
+     */
+    pub fn group_rank(&self, groups: &[String]) -> Option<usize> {
+        groups.iter().position(|group| {
+            if group.is_empty() {
+                self.groups.is_empty()
+            } else {
+                self.groups.contains(group)
+            }
+        })
+    }
+}
+
+/**
+ * Operations on many containers, see [`bulk()`].
+ */
+#[derive(Clone, Debug)]
+pub struct Bulk {
+    names: Vec<String>,
+    lxcpath: Option<PathBuf>,
+    concurrency: usize,
+    groups: Option<Vec<String>>,
+}
+
+/**
+ * Run operations on the `names` containers.
+ *
+ * Containers are handled by decreasing `lxc.start.order` (increasing when
+ * stopping or freezing), those with the same order concurrently.
+ */
+#[must_use]
+pub fn bulk<S: AsRef<str>>(names: &[S]) -> Bulk {
+    Bulk {
+        names: names.iter().map(|x| x.as_ref().to_string()).collect(),
+        lxcpath: None,
+        concurrency: 1,
+        groups: None,
+    }
+}
+
+struct Entry {
+    name: String,
+    settings: crate::Result<StartSettings>,
+    rank: usize,
+}
+
+impl Bulk {
+    #[must_use]
+    pub fn lxcpath(mut self, lxcpath: impl AsRef<Path>) -> Self {
+        self.lxcpath = Some(lxcpath.as_ref().to_path_buf());
+        self
+    }
+
+    /**
+     * Number of containers handled at once, 1 by default.
+     */
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /**
+     * Only handle containers of these `lxc.group`, in this order. An empty
+     * group selects containers without group, like `lxc-autostart -g`.
+     */
+    #[must_use]
+    pub fn groups<S: AsRef<str>>(mut self, groups: &[S]) -> Self {
+        self.groups = Some(groups.iter().map(|x| x.as_ref().to_string()).collect());
+        self
+    }
+
+    /**
+     * Start the containers, waiting `lxc.start.delay` after each order.
+     */
+    pub fn start_all(&self) -> Vec<Outcome> {
+        self.run(&|name| self.container(name), false, true, |container| {
+            container.start(false, &[])
+        })
+    }
+
+    pub fn stop_all(&self, policy: &crate::StopPolicy) -> Vec<Outcome> {
+        self.run(&|name| self.container(name), true, false, |container| {
+            container.stop_gracefully(policy).map(|_| ())
+        })
+    }
+
+    pub fn freeze_all(&self) -> Vec<Outcome> {
+        self.run(
+            &|name| self.container(name),
+            true,
+            false,
+            crate::Container::freeze,
+        )
+    }
+
+    pub fn unfreeze_all(&self) -> Vec<Outcome> {
+        self.run(
+            &|name| self.container(name),
+            false,
+            false,
+            crate::Container::unfreeze,
+        )
+    }
+
+    pub fn snapshot_all(&self, comment: &str) -> Vec<Outcome<crate::SnapshotName>> {
+        self.run(&|name| self.container(name), false, false, |container| {
+            container.snapshot_with_comment(comment)
+        })
+    }
+
+    /**
+     * Run `f` on the containers returned by `open`, level by level.
+     */
+    fn run<C: ContainerApi, T: Send>(
+        &self,
+        open: &(impl Fn(&str) -> crate::Result<C> + Sync),
+        reverse: bool,
+        delay: bool,
+        f: impl Fn(&C) -> crate::Result<T> + Sync,
+    ) -> Vec<Outcome<T>> {
+        let mut outcomes = Vec::new();
+        let mut entries = Vec::new();
+
+        for name in &self.names {
+            let settings = open(name).and_then(|x| StartSettings::load(&x));
+
+            let rank = match (&self.groups, &settings) {
+                (Some(groups), Ok(settings)) => match settings.group_rank(groups) {
+                    Some(rank) => rank,
+                    None => continue,
+                },
+                _ => 0,
+            };
+
+            entries.push(Entry {
+                name: name.clone(),
+                settings,
+                rank,
+            });
+        }
+
+        // Unreadable containers are reported first
+        let (mut entries, broken) = entries
+            .into_iter()
+            .partition::<Vec<_>, _>(|entry| entry.settings.is_ok());
+
+        for entry in broken {
+            if let Err(err) = entry.settings {
+                outcomes.push(Outcome {
+                    name: entry.name,
+                    result: Err(err),
+                });
+            }
+        }
+
+        let order = |entry: &Entry| entry.settings.as_ref().map_or(0, |x| x.order);
+
+        entries.sort_by(|a, b| {
+            let order = if reverse {
+                order(a).cmp(&order(b))
+            } else {
+                order(b).cmp(&order(a))
+            };
+
+            a.rank.cmp(&b.rank).then(order).then(a.name.cmp(&b.name))
+        });
+
+        for level in entries.chunk_by(|a, b| a.rank == b.rank && order(a) == order(b)) {
+            outcomes.extend(self.run_level(level, open, &f));
+
+            let max_delay = level
+                .iter()
+                .filter_map(|entry| entry.settings.as_ref().ok())
+                .map(|settings| settings.delay)
+                .max()
+                .unwrap_or_default();
+
+            if delay && !max_delay.is_zero() {
+                std::thread::sleep(max_delay);
+            }
+        }
+
+        outcomes
+    }
+
+    fn run_level<C: ContainerApi, T: Send>(
+        &self,
+        level: &[Entry],
+        open: &(impl Fn(&str) -> crate::Result<C> + Sync),
+        f: &(impl Fn(&C) -> crate::Result<T> + Sync),
+    ) -> Vec<Outcome<T>> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(level.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let x = next.fetch_add(1, Ordering::Relaxed);
+
+                        let Some(entry) = level.get(x) else {
+                            break;
+                        };
+
+                        let result = open(&entry.name).and_then(|x| f(&x));
+
+                        if let Ok(mut results) = results.lock() {
+                            results.push((x, result));
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap_or_default();
+        results.sort_by_key(|(x, _)| *x);
+
+        results
+            .into_iter()
+            .map(|(x, result)| Outcome {
+                name: level[x].name.clone(),
+                result,
+            })
+            .collect()
+    }
+
+    fn container(&self, name: &str) -> crate::Result<crate::Container> {
+        crate::Container::open(name, self.lxcpath.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, bulk};
+    use crate::{ContainerApi, MockContainer};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    /**
+     * Containers with their `lxc.start.order`, `lxc.group` and
+     * `lxc.start.delay`, others having none.
+     */
+    fn open(name: &str) -> crate::Result<MockContainer> {
+        let (order, group, delay) = match name {
+            "missing" => {
+                return Err(crate::Error {
+                    num: -libc::ENOENT,
+                    str: format!("{name} doesn't exist"),
+                });
+            }
+            "db" | "cache" => ("20", "infra", "0"),
+            "app" => ("10", "web", "1"),
+            "proxy" => ("0", "web", "0"),
+            _ => ("0", "", "0"),
+        };
+
+        Ok(MockContainer::new(name)
+            .with_config_item("lxc.start.order", order)
+            .with_config_item("lxc.group", group)
+            .with_config_item("lxc.start.delay", delay))
+    }
+
+    fn names<T>(outcomes: &[Outcome<T>]) -> Vec<&str> {
+        outcomes.iter().map(|x| x.name.as_str()).collect()
+    }
+
+    fn start(container: &MockContainer) -> crate::Result {
+        container.start(false, &[])
+    }
+
+    #[test]
+    fn order() {
+        let containers = bulk(&["proxy", "tools", "app", "cache", "db"]);
+
+        let outcomes = containers.run(&open, false, false, start);
+        assert_eq!(names(&outcomes), ["cache", "db", "app", "proxy", "tools"]);
+        assert!(outcomes.iter().all(|x| x.result.is_ok()));
+
+        let outcomes = containers.run(&open, true, false, start);
+        assert_eq!(names(&outcomes), ["proxy", "tools", "app", "cache", "db"]);
+
+        let outcomes = containers
+            .clone()
+            .groups(&["web", "infra"])
+            .run(&open, false, false, start);
+        assert_eq!(names(&outcomes), ["app", "proxy", "cache", "db"]);
+
+        let outcomes = containers.groups(&[""]).run(&open, false, false, start);
+        assert_eq!(names(&outcomes), ["tools"]);
+    }
+
+    #[test]
+    fn levels() {
+        let events = Mutex::new(Vec::new());
+
+        bulk(&["proxy", "tools", "app", "cache", "db"])
+            .concurrency(4)
+            .run(&open, false, false, |container| {
+                let event = |x| {
+                    events
+                        .lock()
+                        .unwrap()
+                        .push(format!("{x}{}", container.name()))
+                };
+
+                event('+');
+                std::thread::sleep(Duration::from_millis(20));
+                event('-');
+
+                Ok(())
+            });
+
+        let events = events.into_inner().unwrap();
+        let at = |event: &str| events.iter().position(|x| x == event).unwrap();
+
+        // Containers of a level run together, after the previous level
+        assert!(at("+cache").max(at("+db")) < at("-cache").min(at("-db")));
+        assert!(at("-cache").max(at("-db")) < at("+app"));
+        assert!(at("-app") < at("+proxy").min(at("+tools")));
+        assert!(at("+proxy").max(at("+tools")) < at("-proxy").min(at("-tools")));
+    }
+
+    #[test]
+    fn concurrency() {
+        let active = AtomicUsize::new(0);
+        let max = AtomicUsize::new(0);
+
+        let outcomes =
+            bulk(&["a", "b", "c", "d", "e"])
+                .concurrency(2)
+                .run(&open, false, false, |_| {
+                    let current = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(current, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    active.fetch_sub(1, Ordering::SeqCst);
+
+                    Ok(())
+                });
+
+        assert_eq!(names(&outcomes), ["a", "b", "c", "d", "e"]);
+        assert_eq!(max.into_inner(), 2);
+    }
+
+    #[test]
+    fn delay() {
+        let containers = bulk(&["app", "proxy"]);
+
+        let now = Instant::now();
+        containers.run(&open, false, true, start);
+        assert!(now.elapsed() >= Duration::from_secs(1));
+
+        let now = Instant::now();
+        containers.run(&open, false, false, start);
+        assert!(now.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn errors() {
+        let outcomes = bulk(&["app", "missing", "db"]).run(&open, false, false, |container| {
+            if container.name() == "db" {
+                return Err(crate::Error {
+                    num: -1,
+                    str: "db failed".to_string(),
+                });
+            }
+
+            start(container)
+        });
+
+        assert_eq!(names(&outcomes), ["missing", "db", "app"]);
+        assert_eq!(outcomes[0].result.as_ref().unwrap_err().num, -libc::ENOENT);
+        assert_eq!(outcomes[1].result.as_ref().unwrap_err().str, "db failed");
+        assert!(outcomes[2].result.is_ok());
+    }
+}
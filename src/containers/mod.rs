@@ -1,10 +1,15 @@
+mod bulk;
+
+pub(crate) use bulk::StartSettings;
+pub use bulk::{Bulk, Outcome, bulk};
+
 macro_rules! list_containers {
     ($name:ident, $fn:ident) => {
         #[doc = concat!("Lists ", stringify!($name), " containers within the specified directory path.")]
         /**
          * # Parameters
          *
-         * * `path` - A reference to a string representing the directory path where the containers are located.
+         * * `path` - The directory path where the containers are located.
          *
          * # Return
          *
@@ -21,30 +26,47 @@ macro_rules! list_containers {
          * This function uses unsafe Rust code to interact with the LXC library. It is important to ensure that the
          * provided `path` is a valid directory path and that the LXC library is properly initialized.
          */
-        pub fn $name(path: &str) -> crate::Result<Vec<String>> {
+        pub fn $name(path: impl AsRef<std::path::Path>) -> crate::Result<Vec<String>> {
+            crate::load()?;
+
             let mut names = std::ptr::null_mut();
 
             let size = unsafe {
-                lxc_sys::$fn(cstr!(path), &mut names, std::ptr::null_mut())
+                lxc_sys::$fn(cstr!(path.as_ref()), &mut names, std::ptr::null_mut())
             };
 
             if size < 0 {
+                let errno = std::io::Error::last_os_error()
+                    .raw_os_error()
+                    .filter(|x| *x != 0)
+                    .unwrap_or(libc::EIO);
+
                 return Err(crate::Error {
-                    num: 0,
+                    num: -errno,
                     str: format!("Failed to list {} containers", stringify!($name)),
                 });
             }
 
-            let containers = crate::ffi::vec_from_nta(names)
-                .into_iter()
-                .map(|x| crate::ffi::to_string(x))
-                .collect();
+            if names.is_null() {
+                return Ok(Vec::new());
+            }
+
+            // Every name is copied then freed, even after an invalid one
+            let containers = (0..size as usize)
+                .map(|x| unsafe {
+                    let name = *names.add(x);
+                    let container = crate::ffi::to_string(name);
+                    lxc_sys::free(name as *mut std::ffi::c_void);
+
+                    container
+                })
+                .collect::<Vec<_>>();
 
             unsafe {
                 lxc_sys::free(names as *mut std::ffi::c_void);
             }
 
-            Ok(containers)
+            containers.into_iter().collect()
         }
     }
 }
@@ -0,0 +1,395 @@
+use std::ffi::CString;
+
+/**
+ * Template used to build the container root filesystem.
+ */
+pub enum Template {
+    /** Download a prebuilt image from the images server. */
+    Download {
+        dist: String,
+        release: String,
+        arch: String,
+    },
+    /** Minimal busybox based container. */
+    Busybox,
+    /** Local template script. */
+    Path(std::path::PathBuf),
+    /** OCI image. */
+    Oci { url: String },
+}
+
+impl Template {
+    fn name(&self) -> crate::Result<CString> {
+        match self {
+            Self::Download { .. } => crate::ffi::to_cstr("download"),
+            Self::Busybox => crate::ffi::to_cstr("busybox"),
+            Self::Path(path) => crate::ffi::to_cstr(path.as_os_str()),
+            Self::Oci { .. } => crate::ffi::to_cstr("oci"),
+        }
+    }
+
+    fn args(&self) -> Vec<String> {
+        match self {
+            Self::Download {
+                dist,
+                release,
+                arch,
+            } => vec![
+                "-d".to_string(),
+                dist.clone(),
+                "-r".to_string(),
+                release.clone(),
+                "-a".to_string(),
+                arch.clone(),
+            ],
+            Self::Oci { url } => vec!["--url".to_string(), url.clone()],
+            Self::Busybox | Self::Path(_) => Vec::new(),
+        }
+    }
+}
+
+/**
+ * LVM backing store options.
+ */
+#[derive(Default)]
+pub struct Lvm {
+    /** Volume group, `lxc` by default. */
+    pub vg: Option<String>,
+    /** Logical volume, the container name by default. */
+    pub lv: Option<String>,
+    /** Thin pool to create the volume in. */
+    pub thinpool: Option<String>,
+    /** Filesystem type. */
+    pub fstype: Option<String>,
+    /** Size in bytes. */
+    pub size: Option<u64>,
+}
+
+/**
+ * Ceph RBD backing store options.
+ */
+#[derive(Default)]
+pub struct Rbd {
+    /** Image name, the container name by default. */
+    pub name: Option<String>,
+    /** Pool name, `lxc` by default. */
+    pub pool: Option<String>,
+    /** Filesystem type. */
+    pub fstype: Option<String>,
+    /** Size in bytes. */
+    pub size: Option<u64>,
+}
+
+/**
+ * Loop device backing store options.
+ */
+#[derive(Default)]
+pub struct Loop {
+    /** Filesystem type. */
+    pub fstype: Option<String>,
+    /** Size in bytes. */
+    pub size: Option<u64>,
+}
+
+/**
+ * Backing store of the container root filesystem.
+ */
+pub enum Backend {
+    /** Plain directory, optionally at a custom location. */
+    Dir(Option<std::path::PathBuf>),
+    /** Btrfs subvolume. */
+    Btrfs,
+    /** ZFS dataset, optionally under a custom root. */
+    Zfs(Option<String>),
+    Lvm(Lvm),
+    Rbd(Rbd),
+    Overlay,
+    Loop(Loop),
+}
+
+impl Backend {
+    fn bdevtype(&self) -> &'static str {
+        match self {
+            Self::Dir(_) => "dir",
+            Self::Btrfs => "btrfs",
+            Self::Zfs(_) => "zfs",
+            Self::Lvm(_) => "lvm",
+            Self::Rbd(_) => "rbd",
+            Self::Overlay => "overlay",
+            Self::Loop(_) => "loop",
+        }
+    }
+
+    fn specs(&self) -> crate::Result<Specs> {
+        let mut specs = Specs::default();
+
+        match self {
+            Self::Dir(Some(path)) => {
+                specs.inner.dir = specs.keep(path)?;
+            }
+            Self::Zfs(Some(zfsroot)) => {
+                specs.inner.zfs.zfsroot = specs.keep(zfsroot)?;
+            }
+            Self::Lvm(lvm) => {
+                specs.inner.lvm.vg = specs.keep_option(lvm.vg.as_deref())?;
+                specs.inner.lvm.lv = specs.keep_option(lvm.lv.as_deref())?;
+                specs.inner.lvm.thinpool = specs.keep_option(lvm.thinpool.as_deref())?;
+                specs.inner.fstype = specs.keep_option(lvm.fstype.as_deref())?;
+                specs.inner.fssize = lvm.size.unwrap_or_default();
+            }
+            Self::Rbd(rbd) => {
+                #[cfg(feature = "v2_0")]
+                {
+                    specs.inner.rbd.rbdname = specs.keep_option(rbd.name.as_deref())?;
+                    specs.inner.rbd.rbdpool = specs.keep_option(rbd.pool.as_deref())?;
+                }
+                #[cfg(not(feature = "v2_0"))]
+                if rbd.name.is_some() || rbd.pool.is_some() {
+                    return Err(crate::Error::unsupported("rbd name and pool"));
+                }
+                specs.inner.fstype = specs.keep_option(rbd.fstype.as_deref())?;
+                specs.inner.fssize = rbd.size.unwrap_or_default();
+            }
+            Self::Loop(r#loop) => {
+                specs.inner.fstype = specs.keep_option(r#loop.fstype.as_deref())?;
+                specs.inner.fssize = r#loop.size.unwrap_or_default();
+            }
+            Self::Dir(None) | Self::Zfs(None) | Self::Btrfs | Self::Overlay => (),
+        }
+
+        Ok(specs)
+    }
+}
+
+/**
+ * `bdev_specs` with the strings it points to.
+ */
+pub(crate) struct Specs {
+    strings: Vec<CString>,
+    pub inner: lxc_sys::bdev_specs,
+}
+
+impl Default for Specs {
+    fn default() -> Self {
+        Self {
+            strings: Vec::new(),
+            inner: unsafe { std::mem::zeroed() },
+        }
+    }
+}
+
+impl Specs {
+    fn keep<S: AsRef<std::ffi::OsStr> + ?Sized>(
+        &mut self,
+        s: &S,
+    ) -> crate::Result<*mut std::os::raw::c_char> {
+        let s = crate::ffi::to_cstr(s)?;
+        let ptr = s.as_ptr() as *mut _;
+
+        self.strings.push(s);
+
+        Ok(ptr)
+    }
+
+    fn keep_option(&mut self, s: Option<&str>) -> crate::Result<*mut std::os::raw::c_char> {
+        s.map_or(Ok(std::ptr::null_mut()), |s| self.keep(s))
+    }
+}
+
+/**
+ * Options for `lxc::Container::create_with()`.
+ */
+#[derive(Default)]
+pub struct Options {
+    template: Option<Template>,
+    backend: Option<Backend>,
+    quiet: bool,
+    args: Vec<String>,
+}
+
+impl Options {
+    /**
+     * Create options without template, using the default backing store.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Set the template used to build the root filesystem.
+     */
+    #[must_use]
+    pub fn template(mut self, template: Template) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /**
+     * Set the backing store.
+     */
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /**
+     * Redirect the template output to `/dev/null`.
+     */
+    #[must_use]
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /**
+     * Append an extra argument for the template.
+     */
+    #[must_use]
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    pub(crate) fn template_name(&self) -> crate::Result<Option<CString>> {
+        self.template.as_ref().map(Template::name).transpose()
+    }
+
+    pub(crate) fn bdevtype(&self) -> Option<&'static str> {
+        self.backend.as_ref().map(Backend::bdevtype)
+    }
+
+    pub(crate) fn specs(&self) -> Option<crate::Result<Specs>> {
+        self.backend.as_ref().map(Backend::specs)
+    }
+
+    pub(crate) fn flags(&self) -> crate::CreateFlags {
+        if self.quiet {
+            crate::CreateFlags::QUIET
+        } else {
+            crate::CreateFlags::empty()
+        }
+    }
+
+    pub(crate) fn argv(&self) -> Vec<String> {
+        let mut argv = self
+            .template
+            .as_ref()
+            .map(Template::args)
+            .unwrap_or_default();
+
+        argv.extend(self.args.iter().cloned());
+
+        argv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Lvm, Options, Rbd, Template};
+    use std::ffi::CStr;
+
+    fn string(s: *const std::os::raw::c_char) -> Option<&'static str> {
+        if s.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(s) }.to_str().unwrap())
+        }
+    }
+
+    #[test]
+    fn argv() {
+        let options = Options::new()
+            .template(Template::Download {
+                dist: "debian".to_string(),
+                release: "bookworm".to_string(),
+                arch: "amd64".to_string(),
+            })
+            .arg("--no-validate");
+
+        assert_eq!(
+            options.argv(),
+            [
+                "-d",
+                "debian",
+                "-r",
+                "bookworm",
+                "-a",
+                "amd64",
+                "--no-validate"
+            ]
+        );
+        assert_eq!(
+            options.template_name().unwrap().as_deref(),
+            Some(c"download")
+        );
+
+        let options = Options::new().template(Template::Oci {
+            url: "docker://alpine".to_string(),
+        });
+        assert_eq!(options.argv(), ["--url", "docker://alpine"]);
+
+        assert!(Options::new().argv().is_empty());
+        assert_eq!(Options::new().template_name().unwrap(), None);
+    }
+
+    #[test]
+    fn template_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::OsStr::from_bytes(b"/usr/share/lxc/templates/lxc-\xff");
+        let options = Options::new().template(Template::Path(path.into()));
+
+        assert_eq!(
+            options.template_name().unwrap().unwrap().as_bytes(),
+            path.as_bytes()
+        );
+        assert!(options.argv().is_empty());
+    }
+
+    #[test]
+    fn specs() {
+        assert!(Options::new().specs().is_none());
+        assert_eq!(Options::new().bdevtype(), None);
+
+        let options = Options::new().backend(Backend::Dir(Some("/srv/rootfs".into())));
+        let specs = options.specs().unwrap().unwrap();
+        assert_eq!(options.bdevtype(), Some("dir"));
+        assert_eq!(string(specs.inner.dir), Some("/srv/rootfs"));
+
+        let options = Options::new().backend(Backend::Zfs(Some("tank/lxc".to_string())));
+        let specs = options.specs().unwrap().unwrap();
+        assert_eq!(string(specs.inner.zfs.zfsroot), Some("tank/lxc"));
+
+        let options = Options::new().backend(Backend::Lvm(Lvm {
+            vg: Some("vg0".to_string()),
+            thinpool: Some("pool".to_string()),
+            fstype: Some("ext4".to_string()),
+            size: Some(1 << 30),
+            ..Lvm::default()
+        }));
+        let specs = options.specs().unwrap().unwrap();
+        assert_eq!(options.bdevtype(), Some("lvm"));
+        assert_eq!(string(specs.inner.lvm.vg), Some("vg0"));
+        assert_eq!(string(specs.inner.lvm.lv), None);
+        assert_eq!(string(specs.inner.lvm.thinpool), Some("pool"));
+        assert_eq!(string(specs.inner.fstype), Some("ext4"));
+        assert_eq!(specs.inner.fssize, 1 << 30);
+
+        let options = Options::new().backend(Backend::Rbd(Rbd {
+            pool: Some("rbd".to_string()),
+            ..Rbd::default()
+        }));
+        #[cfg(feature = "v2_0")]
+        assert_eq!(
+            string(options.specs().unwrap().unwrap().inner.rbd.rbdpool),
+            Some("rbd")
+        );
+        #[cfg(not(feature = "v2_0"))]
+        assert!(options.specs().unwrap().is_err_and(|e| e.is_unsupported()));
+
+        let options = Options::new().backend(Backend::Dir(Some("/srv/root\0fs".into())));
+        assert!(options.specs().unwrap().is_err());
+    }
+}
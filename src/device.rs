@@ -0,0 +1,493 @@
+use std::path::{Path, PathBuf};
+
+const MOUNT_KEY: &str = "lxc.mount.entry";
+
+const ALLOW_KEY: &str = "lxc.cgroup.devices.allow";
+#[cfg(feature = "v3_0")]
+const ALLOW_KEY_V2: &str = "lxc.cgroup2.devices.allow";
+
+/**
+ * Whether the host uses a unified cgroup hierarchy, detected once.
+ */
+#[cfg(feature = "v3_0")]
+fn unified() -> bool {
+    static UNIFIED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+    *UNIFIED
+        .get_or_init(|| crate::host::Cgroup::detect().layout == crate::host::CgroupLayout::Unified)
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    Char,
+    Block,
+}
+
+impl Kind {
+    fn as_char(self) -> char {
+        match self {
+            Self::Char => 'c',
+            Self::Block => 'b',
+        }
+    }
+
+    fn mode(self) -> libc::mode_t {
+        match self {
+            Self::Char => libc::S_IFCHR,
+            Self::Block => libc::S_IFBLK,
+        }
+    }
+}
+
+/**
+ * Device node passed through to a container.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Device {
+    pub kind: Kind,
+    pub major: u32,
+    pub minor: u32,
+    /** Cgroup access, a combination of `r`, `w` and `m`. */
+    pub access: String,
+    /** Permissions of the node. */
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /** Path inside the container. */
+    pub path: PathBuf,
+    /** Node on the host, bind mounted in the container when set. */
+    pub host_path: Option<PathBuf>,
+}
+
+impl Device {
+    /**
+     * Create a device readable and writable by root.
+     */
+    #[must_use]
+    pub fn new(kind: Kind, major: u32, minor: u32, path: impl AsRef<Path>) -> Self {
+        Self {
+            kind,
+            major,
+            minor,
+            access: "rwm".to_string(),
+            mode: 0o660,
+            uid: 0,
+            gid: 0,
+            path: path.as_ref().to_path_buf(),
+            host_path: None,
+        }
+    }
+
+    /**
+     * Describe a host device node, at the same path inside the container.
+     */
+    pub fn from_host(path: impl AsRef<Path>) -> crate::Result<Self> {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+        let path = path.as_ref();
+        let metadata = std::fs::metadata(path)?;
+
+        let kind = if metadata.file_type().is_char_device() {
+            Kind::Char
+        } else if metadata.file_type().is_block_device() {
+            Kind::Block
+        } else {
+            return Err(crate::Error {
+                num: crate::Error::INVALID,
+                str: format!("{} isn't a device node", path.display()),
+            });
+        };
+
+        let rdev = metadata.rdev();
+
+        Ok(Self {
+            mode: metadata.mode() & 0o7777,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            host_path: Some(path.to_path_buf()),
+            ..Self::new(kind, libc::major(rdev), libc::minor(rdev), path)
+        })
+    }
+
+    #[must_use]
+    pub fn access(mut self, access: &str) -> Self {
+        self.access = access.to_string();
+        self
+    }
+
+    #[must_use]
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[must_use]
+    pub fn owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+
+    /**
+     * Cgroup rule allowing the device, e.g. `c 188:0 rwm`.
+     */
+    #[must_use]
+    pub fn rule(&self) -> String {
+        format!(
+            "{} {}:{} {}",
+            self.kind.as_char(),
+            self.major,
+            self.minor,
+            self.access
+        )
+    }
+
+    fn mount_entry(&self) -> Option<String> {
+        let host_path = self.host_path.as_ref()?;
+
+        Some(format!(
+            "{} {} none bind,optional,create=file",
+            host_path.display(),
+            self.path.strip_prefix("/").unwrap_or(&self.path).display()
+        ))
+    }
+
+    fn parse_rule(rule: &str) -> Option<Self> {
+        let mut fields = rule.split_whitespace();
+
+        let kind = match fields.next()? {
+            "c" => Kind::Char,
+            "b" => Kind::Block,
+            _ => return None,
+        };
+        let (major, minor) = fields.next()?.split_once(':')?;
+        let access = fields.next().unwrap_or("rwm");
+
+        Some(Self::new(kind, major.parse().ok()?, minor.parse().ok()?, "").access(access))
+    }
+
+    fn same_node(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.major == other.major && self.minor == other.minor
+    }
+}
+
+/**
+ * Devices of a container, see [`crate::Container::devices()`].
+ */
+pub struct Devices<'a> {
+    container: &'a crate::Container,
+}
+
+impl crate::Container {
+    /**
+     * Manage device nodes passed through to the container.
+     */
+    #[must_use]
+    pub fn devices(&self) -> Devices<'_> {
+        Devices { container: self }
+    }
+}
+
+impl Devices<'_> {
+    /**
+     * Pass a device through to the container.
+     *
+     * The cgroup rule, and the bind mount of the host node if any, are saved
+     * in the container config. If the container is running, the node is
+     * created right away.
+     */
+    pub fn add(&self, device: &Device) -> crate::Result {
+        let key = self.allow_key()?;
+        let rules = self.container.get_config_item(key)?.unwrap_or_default();
+
+        self.container.set_config_item(key, &device.rule())?;
+
+        if let Some(entry) = device.mount_entry() {
+            self.container
+                .set_config_item(MOUNT_KEY, &entry)
+                .inspect_err(|_| {
+                    self.replace(key, &rules.lines().collect::<Vec<_>>()).ok();
+                })?;
+        }
+
+        self.save()?;
+
+        if self.container.is_running() {
+            self.with_node(device, |src, dest| {
+                self.container.add_device_node(src, Some(dest))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Stop passing a device through to the container.
+     */
+    pub fn remove(&self, device: &Device) -> crate::Result {
+        self.retain(self.allow_key()?, |value| {
+            Device::parse_rule(value).is_none_or(|other| !other.same_node(device))
+        })?;
+
+        if let Some(entry) = device.mount_entry() {
+            self.retain(MOUNT_KEY, |value| value != entry)?;
+        }
+
+        self.save()?;
+
+        if self.container.is_running() {
+            self.with_node(device, |src, dest| {
+                self.container.remove_device_node(src, Some(dest))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Devices allowed by the container config.
+     *
+     * Wildcard rules are ignored. Paths are resolved from bind mounts of host
+     * nodes and, for a running container, from the nodes in its `/dev`.
+     */
+    pub fn list(&self) -> crate::Result<Vec<Device>> {
+        let mut devices = self
+            .container
+            .get_config_item(self.allow_key()?)?
+            .unwrap_or_default()
+            .lines()
+            .filter_map(Device::parse_rule)
+            .collect::<Vec<_>>();
+
+        let entries = self
+            .container
+            .get_config_item(MOUNT_KEY)?
+            .unwrap_or_default();
+
+        for entry in entries.lines() {
+            let mut fields = entry.split_whitespace();
+            let (Some(src), Some(dest)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+
+            let Ok(host) = Device::from_host(src) else {
+                continue;
+            };
+
+            if let Some(device) = devices.iter_mut().find(|x| x.same_node(&host)) {
+                device.path = Path::new("/").join(dest);
+                device.mode = host.mode;
+                device.uid = host.uid;
+                device.gid = host.gid;
+                device.host_path = host.host_path;
+            }
+        }
+
+        if self.container.is_running() {
+            let root = PathBuf::from(format!("/proc/{}/root", self.container.init_pid()));
+
+            for node in scan_nodes(&root.join("dev")) {
+                if let Some(device) = devices
+                    .iter_mut()
+                    .find(|x| x.path.as_os_str().is_empty() && x.same_node(&node))
+                {
+                    device.path =
+                        Path::new("/").join(node.path.strip_prefix(&root).unwrap_or(&node.path));
+                    device.mode = node.mode;
+                    device.uid = node.uid;
+                    device.gid = node.gid;
+                }
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /**
+     * Config key of the device rules: the one of the cgroup version the
+     * container config already uses, otherwise `lxc.cgroup2` on a unified
+     * host, the devices controller being only available through cgroup v2
+     * there.
+     */
+    fn allow_key(&self) -> crate::Result<&'static str> {
+        #[cfg(feature = "v3_0")]
+        {
+            let has = |key| {
+                self.container
+                    .get_config_item(key)
+                    .map(|x| x.is_some_and(|x| !x.is_empty()))
+            };
+
+            if has("lxc.cgroup2")? {
+                return Ok(ALLOW_KEY_V2);
+            }
+
+            if !has("lxc.cgroup")? && unified() {
+                return Ok(ALLOW_KEY_V2);
+            }
+        }
+
+        Ok(ALLOW_KEY)
+    }
+
+    fn save(&self) -> crate::Result {
+        match self.container.config_file_name() {
+            Some(config) => self.container.save_config(config),
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Keep the values of a multi-valued config key matching `keep`, the
+     * previous values are restored on failure.
+     */
+    fn retain(&self, key: &str, keep: impl Fn(&str) -> bool) -> crate::Result {
+        let values = self.container.get_config_item(key)?.unwrap_or_default();
+        let values = values.lines().collect::<Vec<_>>();
+        let kept = values
+            .iter()
+            .copied()
+            .filter(|value| keep(value))
+            .collect::<Vec<_>>();
+
+        if kept.len() == values.len() {
+            return Ok(());
+        }
+
+        self.replace(key, &kept).inspect_err(|_| {
+            self.replace(key, &values).ok();
+        })
+    }
+
+    fn replace(&self, key: &str, values: &[&str]) -> crate::Result {
+        self.container.clear_config_item(key)?;
+
+        for value in values {
+            self.container.set_config_item(key, value)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Call `f` with a host node of the device, creating a temporary one if
+     * needed.
+     */
+    fn with_node(
+        &self,
+        device: &Device,
+        f: impl FnOnce(&str, &str) -> crate::Result,
+    ) -> crate::Result {
+        let dest = to_str(&device.path)?;
+
+        if let Some(host_path) = &device.host_path {
+            return f(to_str(host_path)?, dest);
+        }
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let node = std::env::temp_dir().join(format!("lxc-device-{}-{nanos}", std::process::id()));
+        let c_node = crate::ffi::to_cstr(&node)?;
+
+        let rdev = libc::makedev(device.major, device.minor);
+        if unsafe { libc::mknod(c_node.as_ptr(), device.kind.mode() | device.mode, rdev) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        let result = std::os::unix::fs::chown(&node, Some(device.uid), Some(device.gid))
+            .map_err(crate::Error::from)
+            .and_then(|()| f(to_str(&node)?, dest));
+
+        std::fs::remove_file(&node).ok();
+
+        result
+    }
+}
+
+fn to_str(path: &Path) -> crate::Result<&str> {
+    path.to_str().ok_or_else(|| crate::Error {
+        num: crate::Error::INVALID,
+        str: format!("{} isn't valid UTF-8", path.display()),
+    })
+}
+
+fn scan_nodes(dir: &Path) -> Vec<Device> {
+    let mut nodes = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => dirs.push(path),
+                Ok(file_type) if !file_type.is_symlink() => {
+                    if let Ok(device) = Device::from_host(&path) {
+                        nodes.push(Device {
+                            path: device.host_path.clone().unwrap_or_default(),
+                            ..device
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Device, Kind};
+
+    #[test]
+    fn rule() {
+        assert_eq!(
+            Device::new(Kind::Char, 188, 0, "/dev/ttyUSB0").rule(),
+            "c 188:0 rwm"
+        );
+        assert_eq!(
+            Device::new(Kind::Block, 8, 16, "/dev/sdb")
+                .access("r")
+                .rule(),
+            "b 8:16 r"
+        );
+    }
+
+    #[test]
+    fn parse_rule() {
+        let device = Device::parse_rule("c 188:0 rw").unwrap();
+
+        assert_eq!(device.kind, Kind::Char);
+        assert_eq!((device.major, device.minor), (188, 0));
+        assert_eq!(device.access, "rw");
+        assert!(device.same_node(&Device::new(Kind::Char, 188, 0, "/dev/ttyUSB0")));
+
+        assert_eq!(Device::parse_rule("b 8:16").unwrap().access, "rwm");
+        assert_eq!(Device::parse_rule("c *:* m"), None);
+        assert_eq!(Device::parse_rule("a"), None);
+        assert_eq!(Device::parse_rule("c 188"), None);
+        assert_eq!(Device::parse_rule(""), None);
+    }
+
+    #[test]
+    fn mount_entry() {
+        let mut device = Device::new(Kind::Char, 188, 0, "/dev/ttyUSB0");
+
+        assert_eq!(device.mount_entry(), None);
+
+        device.host_path = Some("/dev/ttyUSB1".into());
+
+        assert_eq!(
+            device.mount_entry().as_deref(),
+            Some("/dev/ttyUSB1 dev/ttyUSB0 none bind,optional,create=file")
+        );
+    }
+}
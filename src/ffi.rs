@@ -1,27 +1,86 @@
+use std::ffi::{CStr, CString, OsStr, OsString};
 use std::os::raw::c_char;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
 
-pub fn to_cstr(s: &str) -> std::ffi::CString {
-    std::ffi::CString::new(s).unwrap()
+/**
+ * C string with the same bytes as `s`, fails on interior `NUL`.
+ */
+pub fn to_cstr<S: AsRef<OsStr> + ?Sized>(s: &S) -> crate::Result<CString> {
+    Ok(CString::new(s.as_ref().as_bytes())?)
 }
 
+/**
+ * Borrow a temporary C string, valid until the end of the enclosing statement.
+ *
+ * Returns early with an error if the string contains a `NUL` byte.
+ */
 macro_rules! cstr {
     ( $s:expr ) => {
-        $crate::ffi::to_cstr($s).into_raw()
+        $crate::ffi::to_cstr($s)?.as_ptr()
     };
 }
 
+pub fn to_cstr_opt<S: AsRef<OsStr> + ?Sized>(s: Option<&S>) -> crate::Result<Option<CString>> {
+    s.map(to_cstr).transpose()
+}
+
+/**
+ * Pointer to an optional C string, `NULL` if `None`.
+ */
+pub fn opt_ptr(s: Option<&CStr>) -> *const c_char {
+    s.map_or(std::ptr::null(), CStr::as_ptr)
+}
+
+pub fn to_cstr_vec(v: &[&str]) -> crate::Result<Vec<CString>> {
+    v.iter().map(to_cstr).collect()
+}
+
+/**
+ * Null terminated array of pointers to `v` elements.
+ */
+pub fn to_nta(v: &[CString]) -> Vec<*const c_char> {
+    let mut nta = v.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+    nta.push(std::ptr::null());
+
+    nta
+}
+
 #[cfg(feature = "v1_1")]
-pub fn to_mut_cstr(s: &str) -> Vec<c_char> {
-    let mut bytes = s.to_string().into_bytes();
-    bytes.push(0);
+pub fn to_mut_cstr<S: AsRef<OsStr> + ?Sized>(s: &S) -> crate::Result<Vec<c_char>> {
+    let bytes = to_cstr(s)?.into_bytes_with_nul();
 
-    bytes.iter().map(|b| *b as c_char).collect()
+    Ok(bytes.iter().map(|b| *b as c_char).collect())
 }
 
-pub fn to_string(s: *const c_char) -> String {
-    let buffer = unsafe { std::ffi::CStr::from_ptr(s) };
+/**
+ * Copy a C string, fails if it isn't valid UTF-8.
+ */
+pub fn to_string(s: *const c_char) -> crate::Result<String> {
+    let buffer = unsafe { CStr::from_ptr(s) };
+
+    Ok(buffer.to_str()?.to_string())
+}
+
+/**
+ * Copy a C string, replacing invalid UTF-8 sequences.
+ */
+pub fn to_string_lossy(s: *const c_char) -> String {
+    let buffer = unsafe { CStr::from_ptr(s) };
+
+    buffer.to_string_lossy().into_owned()
+}
+
+/**
+ * Copy a C string byte for byte.
+ */
+pub fn to_os_string(s: *const c_char) -> OsString {
+    let buffer = unsafe { CStr::from_ptr(s) };
+
+    OsString::from_vec(buffer.to_bytes().to_vec())
+}
 
-    buffer.to_str().unwrap().to_string()
+pub fn into_string(s: OsString) -> crate::Result<String> {
+    Ok(String::from_utf8(s.into_vec())?)
 }
 
 pub fn vec_from_nta(raw: *mut *mut i8) -> Vec<*mut i8> {
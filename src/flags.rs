@@ -68,3 +68,34 @@ bitflags::bitflags! {
         const MAXFLAGS = lxc_sys::LXC_CREATE_MAXFLAGS as i32;
     }
 }
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct MountFlags: std::os::raw::c_ulong {
+        /** Mount read-only. */
+        const RDONLY = libc::MS_RDONLY;
+        /** Ignore suid and sgid bits. */
+        const NOSUID = libc::MS_NOSUID;
+        /** Disallow access to device special files. */
+        const NODEV = libc::MS_NODEV;
+        /** Disallow program execution. */
+        const NOEXEC = libc::MS_NOEXEC;
+        /** Writes are synced at once. */
+        const SYNCHRONOUS = libc::MS_SYNCHRONOUS;
+        /** Alter flags of a mounted filesystem. */
+        const REMOUNT = libc::MS_REMOUNT;
+        /** Do not update access times. */
+        const NOATIME = libc::MS_NOATIME;
+        /** Do not update directory access times. */
+        const NODIRATIME = libc::MS_NODIRATIME;
+        /** Bind directory at different place. */
+        const BIND = libc::MS_BIND;
+        /** Apply to the whole subtree. */
+        const REC = libc::MS_REC;
+        const PRIVATE = libc::MS_PRIVATE;
+        const SLAVE = libc::MS_SLAVE;
+        const SHARED = libc::MS_SHARED;
+        /** Update access times relative to modification times. */
+        const RELATIME = libc::MS_RELATIME;
+    }
+}
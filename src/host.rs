@@ -0,0 +1,585 @@
+use std::path::{Path, PathBuf};
+
+/**
+ * Host features needed by containers, see [`check()`].
+ */
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub kernel: Option<String>,
+    pub namespaces: Namespaces,
+    pub cgroup: Cgroup,
+    pub user_namespaces: UserNamespaces,
+    pub modules: Modules,
+    pub lsm: Lsm,
+    pub seccomp: Seccomp,
+    /** Path of the `criu` binary, needed by checkpoint and restore. */
+    pub criu: Option<PathBuf>,
+    /** Entries of `/etc/lxc/lxc-usernet`. */
+    pub usernet: Vec<UsernetQuota>,
+}
+
+/**
+ * Namespaces supported by the kernel.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Namespaces {
+    pub mount: bool,
+    pub uts: bool,
+    pub ipc: bool,
+    pub pid: bool,
+    pub net: bool,
+    pub user: bool,
+    pub cgroup: bool,
+    pub time: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CgroupLayout {
+    /** No cgroup hierarchy is mounted. */
+    None,
+    Legacy,
+    Hybrid,
+    Unified,
+}
+
+/**
+ * Cgroup hierarchy of the host.
+ */
+#[derive(Clone, Debug)]
+pub struct Cgroup {
+    pub layout: CgroupLayout,
+    /** Controllers enabled on the host. */
+    pub controllers: Vec<String>,
+    /**
+     * Cgroup v2 controllers available in the cgroup of the current process
+     * (its `cgroup.controllers`), i.e. which it can enable for unprivileged
+     * containers started from it.
+     */
+    pub delegated: Vec<String>,
+}
+
+/**
+ * User namespace limits.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct UserNamespaces {
+    /** `/proc/sys/user/max_user_namespaces`. */
+    pub max: Option<u64>,
+    /** `/proc/sys/kernel/unprivileged_userns_clone`, Debian specific. */
+    pub unprivileged_clone: Option<bool>,
+    /** `/proc/sys/kernel/apparmor_restrict_unprivileged_userns`, Ubuntu specific. */
+    pub apparmor_restricted: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Module {
+    Loaded,
+    BuiltIn,
+    /** Not loaded, but can be. */
+    Available,
+    Missing,
+}
+
+impl Module {
+    #[must_use]
+    pub fn is_usable(self) -> bool {
+        self != Self::Missing
+    }
+}
+
+/**
+ * Kernel modules used for container networking.
+ */
+#[derive(Clone, Debug)]
+pub struct Modules {
+    pub veth: Module,
+    pub macvlan: Module,
+    pub bridge: Module,
+    pub vxlan: Module,
+}
+
+/**
+ * Linux security modules.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Lsm {
+    pub apparmor: bool,
+    /** `Some(enforcing)` if SELinux is enabled. */
+    pub selinux: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Seccomp {
+    pub enabled: bool,
+    pub filter: bool,
+    /** Support of user space notification, see `lxc.seccomp.notify.proxy`. */
+    pub notify: bool,
+}
+
+/**
+ * Line of `/etc/lxc/lxc-usernet`.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsernetQuota {
+    /** User name, or group name prefixed by `@`. */
+    pub owner: String,
+    pub kind: String,
+    pub link: String,
+    pub count: u32,
+}
+
+/**
+ * Inspect the host, like `lxc-checkconfig`.
+ *
+ * Only `/proc`, `/sys` and configuration files are read.
+ */
+#[must_use]
+pub fn check() -> Report {
+    let kernel = read("/proc/sys/kernel/osrelease");
+
+    Report {
+        modules: Modules::detect(kernel.as_deref()),
+        kernel,
+        namespaces: Namespaces::detect(),
+        cgroup: Cgroup::detect(),
+        user_namespaces: UserNamespaces::detect(),
+        lsm: Lsm::detect(),
+        seccomp: Seccomp::detect(),
+        criu: which("criu"),
+        usernet: UsernetQuota::load("/etc/lxc/lxc-usernet"),
+    }
+}
+
+impl Report {
+    /**
+     * Missing features preventing containers to start, in plain words.
+     */
+    #[must_use]
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let namespaces = [
+            ("mount", self.namespaces.mount),
+            ("uts", self.namespaces.uts),
+            ("ipc", self.namespaces.ipc),
+            ("pid", self.namespaces.pid),
+            ("network", self.namespaces.net),
+        ];
+
+        for (name, supported) in namespaces {
+            if !supported {
+                problems.push(format!("{name} namespace isn't supported"));
+            }
+        }
+
+        if self.cgroup.layout == CgroupLayout::None {
+            problems.push("no cgroup hierarchy is mounted".to_string());
+        }
+
+        if !self.modules.veth.is_usable() {
+            problems.push("veth module is missing".to_string());
+        }
+
+        problems
+    }
+
+    /**
+     * Missing features preventing unprivileged containers to start.
+     */
+    #[must_use]
+    pub fn unprivileged_problems(&self) -> Vec<String> {
+        let mut problems = self.problems();
+
+        if !self.namespaces.user {
+            problems.push("user namespace isn't supported".to_string());
+        }
+
+        if self.user_namespaces.max == Some(0) {
+            problems.push("user.max_user_namespaces is 0".to_string());
+        }
+
+        if self.user_namespaces.unprivileged_clone == Some(false) {
+            problems.push("kernel.unprivileged_userns_clone is disabled".to_string());
+        }
+
+        if self.user_namespaces.apparmor_restricted == Some(true) {
+            problems.push(
+                "kernel.apparmor_restrict_unprivileged_userns restricts user namespaces"
+                    .to_string(),
+            );
+        }
+
+        if self.cgroup.layout == CgroupLayout::Unified && self.cgroup.delegated.is_empty() {
+            problems.push("no cgroup controller is delegated to the current user".to_string());
+        }
+
+        problems
+    }
+}
+
+impl Namespaces {
+    fn detect() -> Self {
+        let exists = |name: &str| Path::new("/proc/self/ns").join(name).exists();
+
+        Self {
+            mount: exists("mnt"),
+            uts: exists("uts"),
+            ipc: exists("ipc"),
+            pid: exists("pid"),
+            net: exists("net"),
+            user: exists("user"),
+            cgroup: exists("cgroup"),
+            time: exists("time"),
+        }
+    }
+}
+
+impl Cgroup {
+    pub(crate) fn detect() -> Self {
+        let mounts = read("/proc/self/mounts").unwrap_or_default();
+        let (layout, v2) = Self::parse_mounts(&mounts);
+
+        let controllers = if let (CgroupLayout::Unified, Some(v2)) = (layout, v2) {
+            read(Path::new(v2).join("cgroup.controllers"))
+                .map(|x| Self::parse_controllers(&x))
+                .unwrap_or_default()
+        } else {
+            Self::parse_proc_cgroups(&read("/proc/cgroups").unwrap_or_default())
+        };
+
+        let delegated = Self::parse_own_cgroup(&read("/proc/self/cgroup").unwrap_or_default())
+            .zip(v2)
+            .and_then(|(path, v2)| {
+                read(
+                    Path::new(v2)
+                        .join(path.trim_start_matches('/'))
+                        .join("cgroup.controllers"),
+                )
+            })
+            .map(|x| Self::parse_controllers(&x))
+            .unwrap_or_default();
+
+        Self {
+            layout,
+            controllers,
+            delegated,
+        }
+    }
+
+    /**
+     * Layout and cgroup2 mount point from `/proc/self/mounts`.
+     */
+    fn parse_mounts(mounts: &str) -> (CgroupLayout, Option<&str>) {
+        let mounts = mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().skip(1);
+
+                Some((fields.next()?, fields.next()?))
+            })
+            .collect::<Vec<_>>();
+
+        let v1 = mounts.iter().any(|(_, fstype)| *fstype == "cgroup");
+        let v2 = mounts
+            .iter()
+            .find(|(_, fstype)| *fstype == "cgroup2")
+            .map(|(path, _)| *path);
+
+        let layout = match (v1, v2.is_some()) {
+            (false, false) => CgroupLayout::None,
+            (true, false) => CgroupLayout::Legacy,
+            (true, true) => CgroupLayout::Hybrid,
+            (false, true) => CgroupLayout::Unified,
+        };
+
+        (layout, v2)
+    }
+
+    /**
+     * Enabled controllers listed in `/proc/cgroups`.
+     */
+    fn parse_proc_cgroups(cgroups: &str) -> Vec<String> {
+        cgroups
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+
+                (fields.get(3) == Some(&"1")).then(|| fields[0].to_string())
+            })
+            .collect()
+    }
+
+    /**
+     * Cgroup v2 path of the process from `/proc/<pid>/cgroup`.
+     */
+    fn parse_own_cgroup(cgroup: &str) -> Option<&str> {
+        cgroup.lines().find_map(|line| line.strip_prefix("0::"))
+    }
+
+    fn parse_controllers(controllers: &str) -> Vec<String> {
+        controllers.split_whitespace().map(String::from).collect()
+    }
+}
+
+impl UserNamespaces {
+    fn detect() -> Self {
+        Self {
+            max: read("/proc/sys/user/max_user_namespaces").and_then(|x| x.parse().ok()),
+            unprivileged_clone: read_bool("/proc/sys/kernel/unprivileged_userns_clone"),
+            apparmor_restricted: read_bool(
+                "/proc/sys/kernel/apparmor_restrict_unprivileged_userns",
+            ),
+        }
+    }
+}
+
+impl Modules {
+    fn detect(kernel: Option<&str>) -> Self {
+        let dir = kernel.map(|kernel| Path::new("/lib/modules").join(kernel));
+        let builtin = dir
+            .as_ref()
+            .and_then(|dir| read(dir.join("modules.builtin")))
+            .unwrap_or_default();
+        let dep = dir
+            .as_ref()
+            .and_then(|dir| read(dir.join("modules.dep")))
+            .unwrap_or_default();
+        let loaded = read("/proc/modules").unwrap_or_default();
+
+        Self::parse(&loaded, &builtin, &dep, |name| {
+            Path::new("/sys/module").join(name).exists()
+        })
+    }
+
+    /**
+     * Modules state from `/proc/modules`, `modules.builtin` and
+     * `modules.dep`, `in_sysfs` telling if `/sys/module/<name>` exists.
+     */
+    fn parse(loaded: &str, builtin: &str, dep: &str, in_sysfs: impl Fn(&str) -> bool) -> Self {
+        let module = |name: &str| {
+            let ko = format!("/{name}.ko");
+            let listed = |list: &str| {
+                list.lines()
+                    .filter_map(|line| line.split(':').next())
+                    .any(|path| path.contains(&ko))
+            };
+
+            if loaded
+                .lines()
+                .any(|line| line.split_whitespace().next() == Some(name))
+            {
+                Module::Loaded
+            } else if listed(builtin) {
+                Module::BuiltIn
+            } else if listed(dep) {
+                Module::Available
+            } else if in_sysfs(name) {
+                Module::BuiltIn
+            } else {
+                Module::Missing
+            }
+        };
+
+        Self {
+            veth: module("veth"),
+            macvlan: module("macvlan"),
+            bridge: module("bridge"),
+            vxlan: module("vxlan"),
+        }
+    }
+}
+
+impl Lsm {
+    fn detect() -> Self {
+        Self {
+            apparmor: read("/sys/module/apparmor/parameters/enabled").as_deref() == Some("Y"),
+            selinux: read_bool("/sys/fs/selinux/enforce"),
+        }
+    }
+}
+
+impl Seccomp {
+    fn detect() -> Self {
+        let enabled = read("/proc/self/status")
+            .unwrap_or_default()
+            .lines()
+            .any(|line| line.starts_with("Seccomp:"));
+        let actions = read("/proc/sys/kernel/seccomp/actions_avail");
+
+        Self {
+            enabled,
+            filter: actions.is_some(),
+            notify: actions.is_some_and(|x| x.split_whitespace().any(|x| x == "user_notif")),
+        }
+    }
+}
+
+impl UsernetQuota {
+    fn load(path: &str) -> Vec<Self> {
+        Self::parse(&read(path).unwrap_or_default())
+    }
+
+    fn parse(usernet: &str) -> Vec<Self> {
+        usernet
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+
+                let [owner, kind, link, count] = fields.as_slice() else {
+                    return None;
+                };
+
+                Some(Self {
+                    owner: owner.to_string(),
+                    kind: kind.to_string(),
+                    link: link.to_string(),
+                    count: count.parse().ok()?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|x| x.trim().to_string())
+}
+
+fn read_bool(path: &str) -> Option<bool> {
+    read(path).map(|x| x != "0")
+}
+
+fn which(program: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .chain(["/usr/sbin".into(), "/sbin".into()])
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cgroup, CgroupLayout, Module, Modules, UsernetQuota};
+
+    #[test]
+    fn mounts() {
+        let unified = "\
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+cgroup2 /sys/fs/cgroup cgroup2 rw,nosuid,nodev,noexec,relatime,nsdelegate 0 0
+";
+        assert_eq!(
+            Cgroup::parse_mounts(unified),
+            (CgroupLayout::Unified, Some("/sys/fs/cgroup"))
+        );
+
+        let hybrid = "\
+tmpfs /sys/fs/cgroup tmpfs ro,nosuid,nodev,noexec,mode=755 0 0
+cgroup2 /sys/fs/cgroup/unified cgroup2 rw,nosuid,nodev,noexec,relatime 0 0
+cgroup /sys/fs/cgroup/memory cgroup rw,nosuid,nodev,noexec,relatime,memory 0 0
+";
+        assert_eq!(
+            Cgroup::parse_mounts(hybrid),
+            (CgroupLayout::Hybrid, Some("/sys/fs/cgroup/unified"))
+        );
+
+        let legacy = "cgroup /sys/fs/cgroup/cpu cgroup rw,cpu,cpuacct 0 0\n";
+        assert_eq!(Cgroup::parse_mounts(legacy), (CgroupLayout::Legacy, None));
+        assert_eq!(Cgroup::parse_mounts(""), (CgroupLayout::None, None));
+    }
+
+    #[test]
+    fn proc_cgroups() {
+        let cgroups = "\
+#subsys_name\thierarchy\tnum_cgroups\tenabled
+cpuset\t0\t98\t1
+cpu\t0\t98\t1
+memory\t0\t98\t0
+";
+
+        assert_eq!(Cgroup::parse_proc_cgroups(cgroups), ["cpuset", "cpu"]);
+    }
+
+    #[test]
+    fn own_cgroup() {
+        let unified = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            Cgroup::parse_own_cgroup(unified),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+
+        let hybrid = "\
+12:memory:/user.slice
+1:name=systemd:/user.slice/user-1000.slice/session-2.scope
+0::/user.slice/user-1000.slice/session-2.scope
+";
+        assert_eq!(
+            Cgroup::parse_own_cgroup(hybrid),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+
+        assert_eq!(Cgroup::parse_own_cgroup("4:cpu,cpuacct:/\n"), None);
+        assert_eq!(
+            Cgroup::parse_controllers("cpuset cpu io memory pids\n"),
+            ["cpuset", "cpu", "io", "memory", "pids"]
+        );
+    }
+
+    #[test]
+    fn usernet() {
+        let usernet = "\
+# USERNAME TYPE BRIDGE COUNT
+alice veth lxcbr0 10
+@lxd  veth br0    2
+bob veth lxcbr0 many
+carol veth
+";
+
+        assert_eq!(
+            UsernetQuota::parse(usernet),
+            [
+                UsernetQuota {
+                    owner: "alice".to_string(),
+                    kind: "veth".to_string(),
+                    link: "lxcbr0".to_string(),
+                    count: 10,
+                },
+                UsernetQuota {
+                    owner: "@lxd".to_string(),
+                    kind: "veth".to_string(),
+                    link: "br0".to_string(),
+                    count: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn modules() {
+        let loaded = "\
+veth 40960 0 - Live 0x0000000000000000
+bridge 421888 1 br_netfilter, Live 0x0000000000000000
+";
+        let builtin = "kernel/drivers/net/macvlan.ko\n";
+        let dep = "\
+kernel/drivers/net/vxlan/vxlan.ko.zst: kernel/net/ipv6/ip6_udp_tunnel.ko.zst
+kernel/net/bridge/br_netfilter.ko.zst: kernel/net/bridge/bridge.ko.zst
+";
+
+        let modules = Modules::parse(loaded, builtin, dep, |_| false);
+
+        assert_eq!(modules.veth, Module::Loaded);
+        assert_eq!(modules.bridge, Module::Loaded);
+        assert_eq!(modules.macvlan, Module::BuiltIn);
+        assert_eq!(modules.vxlan, Module::Available);
+
+        let modules = Modules::parse("", "", "", |name| name == "bridge");
+
+        assert_eq!(modules.bridge, Module::BuiltIn);
+        assert_eq!(modules.veth, Module::Missing);
+        assert!(!modules.veth.is_usable());
+    }
+}
@@ -0,0 +1,743 @@
+use std::path::Path;
+
+#[cfg(feature = "v2_1")]
+const KEY: &str = "lxc.idmap";
+#[cfg(not(feature = "v2_1"))]
+const KEY: &str = "lxc.id_map";
+
+/**
+ * Kind of id.
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    Uid,
+    Gid,
+}
+
+impl Kind {
+    fn as_char(self) -> char {
+        match self {
+            Self::Uid => 'u',
+            Self::Gid => 'g',
+        }
+    }
+}
+
+/**
+ * Contiguous range of ids.
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Range {
+    pub start: u32,
+    pub count: u32,
+}
+
+impl Range {
+    #[must_use]
+    pub fn new(start: u32, count: u32) -> Self {
+        Self { start, count }
+    }
+
+    /**
+     * First id after the range.
+     */
+    #[must_use]
+    pub fn end(&self) -> u64 {
+        u64::from(self.start) + u64::from(self.count)
+    }
+
+    #[must_use]
+    pub fn contains(&self, id: u32) -> bool {
+        id >= self.start && u64::from(id) < self.end()
+    }
+
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        u64::from(self.start) < other.end() && u64::from(other.start) < self.end()
+    }
+
+    /**
+     * Whether `other` is entirely inside this range.
+     */
+    #[must_use]
+    pub fn includes(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end() <= self.end()
+    }
+}
+
+/**
+ * Line of `/etc/subuid` or `/etc/subgid`.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubId {
+    /** User name or numeric uid. */
+    pub owner: String,
+    pub range: Range,
+}
+
+/**
+ * Subordinate ids delegated to users.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct SubIds {
+    entries: Vec<SubId>,
+}
+
+impl SubIds {
+    /**
+     * Parse the content of a subordinate id file.
+     */
+    pub fn parse(content: &str) -> crate::Result<Self> {
+        let mut entries = Vec::new();
+
+        for (x, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || crate::Error {
+                num: crate::Error::INVALID,
+                str: format!("Invalid subordinate id line {}: {line}", x + 1),
+            };
+
+            let mut fields = line.split(':');
+            let owner = fields
+                .next()
+                .filter(|x| !x.is_empty())
+                .ok_or_else(invalid)?;
+            let start = fields
+                .next()
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(invalid)?;
+            let count = fields
+                .next()
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(invalid)?;
+
+            entries.push(SubId {
+                owner: owner.to_string(),
+                range: Range::new(start, count),
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /**
+     * Read a subordinate id file.
+     */
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /**
+     * Read `/etc/subuid`.
+     */
+    pub fn subuid() -> crate::Result<Self> {
+        Self::load("/etc/subuid")
+    }
+
+    /**
+     * Read `/etc/subgid`.
+     */
+    pub fn subgid() -> crate::Result<Self> {
+        Self::load("/etc/subgid")
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[SubId] {
+        &self.entries
+    }
+
+    /**
+     * Ranges delegated to a user, given by name and/or numeric id.
+     */
+    #[must_use]
+    pub fn ranges(&self, name: &str, id: Option<u32>) -> Vec<Range> {
+        let id = id.map(|id| id.to_string());
+
+        self.entries
+            .iter()
+            .filter(|entry| entry.owner == name || Some(&entry.owner) == id.as_ref())
+            .map(|entry| entry.range)
+            .collect()
+    }
+
+    /**
+     * Find `count` ids delegated to a user, not overlapping `used`.
+     */
+    #[must_use]
+    pub fn allocate(
+        &self,
+        name: &str,
+        id: Option<u32>,
+        count: u32,
+        used: &[Range],
+    ) -> Option<Range> {
+        let mut used = used.to_vec();
+        used.sort();
+
+        for range in self.ranges(name, id) {
+            let mut start = u64::from(range.start);
+
+            for other in &used {
+                if start + u64::from(count) <= u64::from(other.start) {
+                    break;
+                }
+
+                if other.end() > start {
+                    start = other.end();
+                }
+            }
+
+            if start + u64::from(count) <= range.end() {
+                return Some(Range::new(start as u32, count));
+            }
+        }
+
+        None
+    }
+}
+
+/**
+ * One `lxc.idmap` entry: `count` ids starting at `container` in the
+ * container are mapped to ids starting at `host` on the host.
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Entry {
+    pub kind: Kind,
+    pub container: u32,
+    pub host: u32,
+    pub count: u32,
+}
+
+impl Entry {
+    #[must_use]
+    pub fn container_range(&self) -> Range {
+        Range::new(self.container, self.count)
+    }
+
+    #[must_use]
+    pub fn host_range(&self) -> Range {
+        Range::new(self.host, self.count)
+    }
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.kind.as_char(),
+            self.container,
+            self.host,
+            self.count
+        )
+    }
+}
+
+impl std::str::FromStr for Entry {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let invalid = || crate::Error {
+            num: crate::Error::INVALID,
+            str: format!("Invalid idmap entry: {s}"),
+        };
+
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+
+        let [kind, container, host, count] = fields.as_slice() else {
+            return Err(invalid());
+        };
+
+        let kind = match *kind {
+            "u" => Kind::Uid,
+            "g" => Kind::Gid,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            kind,
+            container: container.parse().map_err(|_| invalid())?,
+            host: host.parse().map_err(|_| invalid())?,
+            count: count.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/**
+ * Id mapping of a container.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IdMap {
+    entries: Vec<Entry>,
+}
+
+impl IdMap {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Map container ids from 0 to the given host ranges.
+     */
+    #[must_use]
+    pub fn from_ranges(uids: Range, gids: Range) -> Self {
+        Self::new()
+            .entry(Entry {
+                kind: Kind::Uid,
+                container: 0,
+                host: uids.start,
+                count: uids.count,
+            })
+            .entry(Entry {
+                kind: Kind::Gid,
+                container: 0,
+                host: gids.start,
+                count: gids.count,
+            })
+    }
+
+    /**
+     * Allocate `count` uids and gids delegated to a user, avoiding the
+     * ranges used by `others`.
+     */
+    pub fn allocate(
+        subuid: &SubIds,
+        subgid: &SubIds,
+        name: &str,
+        id: Option<u32>,
+        count: u32,
+        others: &[Self],
+    ) -> crate::Result<Self> {
+        let used = |kind| {
+            others
+                .iter()
+                .flat_map(|idmap| idmap.entries(kind))
+                .map(|entry| entry.host_range())
+                .collect::<Vec<_>>()
+        };
+
+        let exhausted = |kind| crate::Error {
+            num: -libc::ENOSPC,
+            str: format!("Not enough subordinate {kind} delegated to {name}"),
+        };
+
+        let uids = subuid
+            .allocate(name, id, count, &used(Kind::Uid))
+            .ok_or_else(|| exhausted("uids"))?;
+        let gids = subgid
+            .allocate(name, id, count, &used(Kind::Gid))
+            .ok_or_else(|| exhausted("gids"))?;
+
+        Ok(Self::from_ranges(uids, gids))
+    }
+
+    /**
+     * Parse `lxc.idmap` values, one per line.
+     */
+    pub fn parse(value: &str) -> crate::Result<Self> {
+        let entries = value
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::parse)
+            .collect::<crate::Result<_>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /**
+     * Read the id mapping of a container.
+     */
+    pub fn from_container(container: &crate::Container) -> crate::Result<Self> {
+        Self::parse(&container.get_config_item(KEY)?.unwrap_or_default())
+    }
+
+    #[must_use]
+    pub fn entry(mut self, entry: Entry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /**
+     * Entries of a kind.
+     */
+    pub fn entries(&self, kind: Kind) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(move |entry| entry.kind == kind)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /**
+     * Config lines, e.g. `lxc.idmap = u 0 100000 65536`.
+     */
+    #[must_use]
+    pub fn render(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| format!("{KEY} = {entry}"))
+            .collect()
+    }
+
+    /**
+     * Replace the id mapping of a container.
+     */
+    pub fn apply(&self, container: &crate::Container) -> crate::Result {
+        container.clear_config_item(KEY)?;
+
+        for entry in &self.entries {
+            container.set_config_item(KEY, &entry.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Host id of a container id, `None` if unmapped or beyond `u32::MAX`.
+     */
+    #[must_use]
+    pub fn host_id(&self, kind: Kind, id: u32) -> Option<u32> {
+        self.entries(kind)
+            .find(|entry| entry.container_range().contains(id))
+            .and_then(|entry| entry.host.checked_add(id - entry.container))
+    }
+
+    /**
+     * Container id of a host id, `None` if unmapped or beyond `u32::MAX`.
+     */
+    #[must_use]
+    pub fn container_id(&self, kind: Kind, id: u32) -> Option<u32> {
+        self.entries(kind)
+            .find(|entry| entry.host_range().contains(id))
+            .and_then(|entry| entry.container.checked_add(id - entry.host))
+    }
+
+    /**
+     * Check the mapping is usable: non empty, non overlapping entries
+     * mapping the container root user and group.
+     */
+    pub fn validate(&self) -> crate::Result {
+        let mut problems = Vec::new();
+
+        for kind in [Kind::Uid, Kind::Gid] {
+            let entries = self.entries(kind).collect::<Vec<_>>();
+
+            for (x, entry) in entries.iter().enumerate() {
+                if entry.count == 0 {
+                    problems.push(format!("{entry}: empty range"));
+                }
+
+                if entry.container_range().end() > u64::from(u32::MAX)
+                    || entry.host_range().end() > u64::from(u32::MAX)
+                {
+                    problems.push(format!("{entry}: range overflows"));
+                }
+
+                for other in &entries[x + 1..] {
+                    if entry.container_range().overlaps(&other.container_range()) {
+                        problems.push(format!("{entry} and {other}: container ids overlap"));
+                    }
+
+                    if entry.host_range().overlaps(&other.host_range()) {
+                        problems.push(format!("{entry} and {other}: host ids overlap"));
+                    }
+                }
+            }
+
+            if self.host_id(kind, 0).is_none() {
+                problems.push(format!("container root {kind:?} isn't mapped"));
+            }
+        }
+
+        Self::result(problems)
+    }
+
+    /**
+     * Check the host ids are delegated to a user.
+     */
+    pub fn validate_for(
+        &self,
+        subuid: &SubIds,
+        subgid: &SubIds,
+        name: &str,
+        id: Option<u32>,
+    ) -> crate::Result {
+        let mut problems = Vec::new();
+
+        for (kind, subids) in [(Kind::Uid, subuid), (Kind::Gid, subgid)] {
+            let ranges = subids.ranges(name, id);
+
+            for entry in self.entries(kind) {
+                if !ranges
+                    .iter()
+                    .any(|range| range.includes(&entry.host_range()))
+                {
+                    problems.push(format!("{entry}: host ids aren't delegated to {name}"));
+                }
+            }
+        }
+
+        Self::result(problems)
+    }
+
+    /**
+     * Change the ownership of a root filesystem from container ids to the
+     * mapped host ids.
+     *
+     * Ids without mapping are left untouched, hard linked files are changed
+     * once. Setuid and setgid bits are restored, but file capabilities are
+     * dropped by the kernel.
+     */
+    pub fn shift(&self, rootfs: impl AsRef<Path>) -> crate::Result {
+        chown_tree(rootfs.as_ref(), &|kind, id| self.host_id(kind, id))
+    }
+
+    /**
+     * Reverse of [`IdMap::shift()`].
+     */
+    pub fn unshift(&self, rootfs: impl AsRef<Path>) -> crate::Result {
+        chown_tree(rootfs.as_ref(), &|kind, id| self.container_id(kind, id))
+    }
+
+    fn result(problems: Vec<String>) -> crate::Result {
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::Error {
+                num: crate::Error::INVALID,
+                str: problems.join(", "),
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for IdMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in self.render() {
+            writeln!(f, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn chown_tree(root: &Path, map: &dyn Fn(Kind, u32) -> Option<u32>) -> crate::Result {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let mut paths = vec![root.to_path_buf()];
+    let mut visited = std::collections::HashSet::new();
+
+    while let Some(path) = paths.pop() {
+        let metadata = std::fs::symlink_metadata(&path)?;
+
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(&path)? {
+                paths.push(entry?.path());
+            }
+        } else if metadata.nlink() > 1 && !visited.insert((metadata.dev(), metadata.ino())) {
+            continue;
+        }
+
+        let uid = map(Kind::Uid, metadata.uid());
+        let gid = map(Kind::Gid, metadata.gid());
+
+        if uid.is_none() && gid.is_none() {
+            continue;
+        }
+
+        std::os::unix::fs::lchown(&path, uid, gid)?;
+
+        if !metadata.is_symlink() && metadata.mode() & 0o6000 != 0 {
+            std::fs::set_permissions(&path, PermissionsExt::from_mode(metadata.mode() & 0o7777))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, IdMap, Kind, Range, SubIds};
+
+    #[test]
+    fn subids() {
+        let subids = SubIds::parse(
+            "# comment\n\nalice:100000:65536\n1000:200000:65536\n  bob:300000:1000  \n",
+        )
+        .unwrap();
+
+        assert_eq!(subids.entries().len(), 3);
+        assert_eq!(
+            subids.ranges("alice", Some(1000)),
+            [Range::new(100_000, 65536), Range::new(200_000, 65536)]
+        );
+        assert_eq!(subids.ranges("bob", None), [Range::new(300_000, 1000)]);
+        assert!(subids.ranges("carol", None).is_empty());
+    }
+
+    #[test]
+    fn subids_malformed() {
+        for content in [
+            "alice",
+            "alice:100000",
+            ":100000:65536",
+            "alice:-1:65536",
+            "alice:100000:many",
+            "alice:4294967296:1",
+        ] {
+            let err = SubIds::parse(content).unwrap_err();
+
+            assert_eq!(err.num, crate::Error::INVALID, "{content}");
+            assert!(err.str.contains("line 1"), "{content}");
+        }
+
+        let err = SubIds::parse("alice:100000:65536\nbob").unwrap_err();
+        assert!(err.str.contains("line 2"));
+    }
+
+    #[test]
+    fn subids_allocate() {
+        let subids = SubIds::parse("alice:100000:65536\nalice:300000:65536").unwrap();
+
+        assert_eq!(
+            subids.allocate("alice", None, 1000, &[]),
+            Some(Range::new(100_000, 1000))
+        );
+        assert_eq!(
+            subids.allocate("alice", None, 1000, &[Range::new(100_000, 1000)]),
+            Some(Range::new(101_000, 1000))
+        );
+        assert_eq!(
+            subids.allocate("alice", None, 65536, &[Range::new(150_000, 1)]),
+            Some(Range::new(300_000, 65536))
+        );
+        assert_eq!(subids.allocate("bob", None, 1, &[]), None);
+    }
+
+    #[test]
+    fn entry() {
+        let entry = "u 0 100000 65536".parse::<Entry>().unwrap();
+
+        assert_eq!(
+            entry,
+            Entry {
+                kind: Kind::Uid,
+                container: 0,
+                host: 100_000,
+                count: 65536,
+            }
+        );
+        assert_eq!(entry.to_string(), "u 0 100000 65536");
+
+        for s in [
+            "",
+            "u 0 100000",
+            "x 0 100000 65536",
+            "g 0 -1 65536",
+            "u 0 1 2 3",
+        ] {
+            assert_eq!(
+                s.parse::<Entry>().unwrap_err().num,
+                crate::Error::INVALID,
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let idmap = IdMap::parse("u 0 100000 65536\n\n  g 0 100000 65536\n").unwrap();
+
+        assert_eq!(
+            idmap,
+            IdMap::from_ranges(Range::new(100_000, 65536), Range::new(100_000, 65536))
+        );
+        assert_eq!(idmap.entries(Kind::Uid).count(), 1);
+        assert!(IdMap::parse("u 0 100000 65536\ng 0 100000").is_err());
+        assert!(IdMap::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn ids() {
+        let idmap = IdMap::parse("u 0 100000 1000\nu 1000 1000 1\ng 0 100000 65536").unwrap();
+
+        assert_eq!(idmap.host_id(Kind::Uid, 0), Some(100_000));
+        assert_eq!(idmap.host_id(Kind::Uid, 999), Some(100_999));
+        assert_eq!(idmap.host_id(Kind::Uid, 1000), Some(1000));
+        assert_eq!(idmap.host_id(Kind::Uid, 1001), None);
+        assert_eq!(idmap.container_id(Kind::Uid, 100_999), Some(999));
+        assert_eq!(idmap.container_id(Kind::Uid, 99_999), None);
+        assert_eq!(idmap.container_id(Kind::Gid, 165_535), Some(65535));
+    }
+
+    #[test]
+    fn ids_overflow() {
+        let idmap = IdMap::parse("u 0 4294967290 100\ng 4294967290 0 100").unwrap();
+
+        assert_eq!(idmap.host_id(Kind::Uid, 5), Some(u32::MAX));
+        assert_eq!(idmap.host_id(Kind::Uid, 6), None);
+        assert_eq!(idmap.container_id(Kind::Gid, 5), Some(u32::MAX));
+        assert_eq!(idmap.container_id(Kind::Gid, 6), None);
+    }
+
+    #[test]
+    fn shift_hard_links() {
+        use std::os::unix::fs::MetadataExt;
+
+        let rootfs =
+            std::env::temp_dir().join(format!("lxc-idmap-{}-hard-links", std::process::id()));
+        std::fs::remove_dir_all(&rootfs).ok();
+        std::fs::create_dir_all(&rootfs).unwrap();
+        std::fs::write(rootfs.join("a"), "").unwrap();
+        std::fs::hard_link(rootfs.join("a"), rootfs.join("b")).unwrap();
+        std::os::unix::fs::lchown(rootfs.join("a"), Some(0), Some(0)).unwrap();
+
+        // Shifting twice would map 0 to 1000 then 1000 to 2000
+        let idmap = IdMap::parse("u 0 1000 1000\nu 1000 2000 1000\ng 0 1000 2000").unwrap();
+        idmap.shift(&rootfs).unwrap();
+
+        let metadata = std::fs::metadata(rootfs.join("b")).unwrap();
+        assert_eq!((metadata.uid(), metadata.gid()), (1000, 1000));
+
+        std::fs::remove_dir_all(&rootfs).unwrap();
+    }
+
+    #[test]
+    fn validate() {
+        assert!(
+            IdMap::parse("u 0 100000 65536\ng 0 100000 65536")
+                .unwrap()
+                .validate()
+                .is_ok()
+        );
+
+        let err = IdMap::parse("u 0 100000 65536\nu 1000 200000 1\ng 1 100000 0")
+            .unwrap()
+            .validate()
+            .unwrap_err();
+
+        assert_eq!(err.num, crate::Error::INVALID);
+        assert!(err.str.contains("container ids overlap"));
+        assert!(err.str.contains("empty range"));
+        assert!(err.str.contains("container root Gid isn't mapped"));
+    }
+
+    #[test]
+    fn validate_for() {
+        let subids = SubIds::parse("alice:100000:65536").unwrap();
+        let idmap = IdMap::parse("u 0 100000 65536\ng 0 165536 1").unwrap();
+        let err = idmap
+            .validate_for(&subids, &subids, "alice", None)
+            .unwrap_err();
+
+        assert_eq!(err.str, "g 0 165536 1: host ids aren't delegated to alice");
+    }
+}
@@ -3,25 +3,57 @@
 #[macro_use]
 mod ffi;
 
+mod api;
+#[cfg(feature = "archive")]
+mod archive;
 pub mod attach;
+pub mod autostart;
 mod console;
 mod container;
 pub mod containers;
+pub mod create;
+mod device;
 mod flags;
+pub mod host;
+pub mod idmap;
 pub mod log;
 mod migrate;
-
+mod mock;
+mod mount;
+pub mod seccomp;
+mod signal;
+pub mod snapshot;
+mod stop;
+pub mod topology;
+mod version;
+
+pub use api::ContainerApi;
+#[cfg(feature = "archive")]
+pub use archive::{Compression, import};
+#[cfg(feature = "v5_0")]
+pub use console::Pty;
 pub use container::Container;
-pub use flags::{AttchFlags, CloneFlags, CreateFlags};
+pub use create::Options as CreateOptions;
+pub use device::{Device, Devices, Kind as DeviceKind};
+pub use flags::{AttchFlags, CloneFlags, CreateFlags, MountFlags};
 pub use log::Log;
+pub use mock::MockContainer;
+#[cfg(feature = "v3_1")]
+pub use mount::Guard as MountGuard;
+pub use mount::Options as MountOptions;
+pub use seccomp::Profile as SeccompProfile;
+pub use signal::Signal;
+pub use snapshot::{Diff as SnapshotDiff, Name as SnapshotName, RetentionPolicy, Snapshot};
+pub use stop::{Policy as StopPolicy, Step as StopStep};
+pub use topology::Topology;
+pub use version::{Capabilities, Version};
 
 pub use lxc_sys::lxc_conf as Conf;
 pub use lxc_sys::lxc_lock as Lock;
 #[cfg(feature = "v3_1")]
 pub use lxc_sys::lxc_mount as Mount;
-pub use lxc_sys::lxc_snapshot as Snapshot;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Error {
     pub num: i32,
     pub str: String,
@@ -35,44 +67,151 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self {
+            num: -err.raw_os_error().unwrap_or(1),
+            str: err.to_string(),
+        }
+    }
+}
+
+impl From<std::ffi::NulError> for Error {
+    fn from(err: std::ffi::NulError) -> Self {
+        Self {
+            num: Self::INVALID,
+            str: err.to_string(),
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self {
+            num: Self::INVALID,
+            str: err.to_string(),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        Self {
+            num: Self::INVALID,
+            str: err.to_string(),
+        }
+    }
+}
+
+impl Error {
+    /**
+     * Error number used when a string can't be passed to or read from liblxc.
+     */
+    pub const INVALID: i32 = -22;
+
+    /**
+     * Error number used when the linked liblxc doesn't provide an API.
+     */
+    pub const UNSUPPORTED: i32 = -38;
+
+    /**
+     * Create an error for an API missing from the linked liblxc.
+     */
+    #[must_use]
+    pub fn unsupported(api: &str) -> Self {
+        Self {
+            num: Self::UNSUPPORTED,
+            str: format!("{api} is not supported by this version of liblxc"),
+        }
+    }
+
+    /**
+     * Whether this error was raised because liblxc is too old.
+     */
+    #[must_use]
+    pub fn is_unsupported(&self) -> bool {
+        self.num == Self::UNSUPPORTED
+    }
+}
+
 pub type Result<T = ()> = std::result::Result<T, Error>;
 
+/**
+ * Load liblxc when built with the `dlopen` feature.
+ *
+ * This is a no-op otherwise.
+ */
+pub fn load() -> Result {
+    #[cfg(feature = "dlopen")]
+    lxc_sys::load().map_err(|str| Error {
+        num: -libc::ELIBACC,
+        str,
+    })?;
+
+    Ok(())
+}
+
 /**
  * Determine version of LXC.
  */
-#[must_use]
-pub fn version() -> String {
-    let version = unsafe { std::ffi::CStr::from_ptr(lxc_sys::lxc_get_version()) };
+pub fn version() -> Result<Version> {
+    load()?;
 
-    version.to_str().unwrap().to_string()
+    let version = ffi::to_string_lossy(unsafe { lxc_sys::lxc_get_version() });
+
+    Ok(version.as_str().into())
+}
+
+/**
+ * Determine optional features supported by the running LXC.
+ */
+pub fn capabilities() -> Result<Capabilities> {
+    Capabilities::detect()
 }
 
 /**
  * Obtain a list of all container states.
  */
-#[must_use]
-pub fn wait_states() -> Vec<String> {
+pub fn wait_states() -> Result<Vec<String>> {
+    load()?;
+
     let size = unsafe { lxc_sys::lxc_get_wait_states(std::ptr::null_mut()) };
 
-    let mut states = Vec::new();
-    states.resize(size as usize, std::ptr::null());
+    let Ok(size) = usize::try_from(size) else {
+        return Err(Error {
+            num: size,
+            str: "Failed to get wait states".to_string(),
+        });
+    };
+
+    let mut states = vec![std::ptr::null(); size];
 
     unsafe { lxc_sys::lxc_get_wait_states(states.as_mut_ptr()) };
 
-    states.iter().map(|e| ffi::to_string(*e)).collect()
+    Ok(states.iter().map(|e| ffi::to_string_lossy(*e)).collect())
 }
 
 /**
  * Get the value for a global config key.
  */
-#[must_use]
-pub fn get_global_config_item(key: &str) -> Option<String> {
+pub fn get_global_config_item(key: &str) -> Result<Option<String>> {
+    get_global_config_item_os(key)?
+        .map(ffi::into_string)
+        .transpose()
+}
+
+/**
+ * Get the value for a global config key, without requiring UTF-8.
+ */
+pub fn get_global_config_item_os(key: &str) -> Result<Option<std::ffi::OsString>> {
+    load()?;
+
     let value = unsafe { lxc_sys::lxc_get_global_config_item(cstr!(key)) };
 
     if value.is_null() {
-        None
+        Ok(None)
     } else {
-        Some(ffi::to_string(value))
+        Ok(Some(ffi::to_os_string(value)))
     }
 }
 
@@ -80,8 +219,10 @@ pub fn get_global_config_item(key: &str) -> Option<String> {
  * Check if the configuration item is supported by this LXC instance.
  */
 #[cfg(feature = "v2_1")]
-pub fn config_item_is_supported(key: &str) -> bool {
-    unsafe { lxc_sys::lxc_config_item_is_supported(cstr!(key)) }
+pub fn config_item_is_supported(key: &str) -> Result<bool> {
+    load()?;
+
+    Ok(unsafe { lxc_sys::lxc_config_item_is_supported(cstr!(key)) })
 }
 
 /**
@@ -92,12 +233,15 @@ pub fn config_item_is_supported(key: &str) -> bool {
  *
  * # Return
  *
- * * `Option<String>` - Returns an `Option` containing a string representing the LXC path.
- *   - `Some(String)` - If the global configuration item is found and its value is not empty,
+ * * `Option<PathBuf>` - Returns an `Option` containing the LXC path.
+ *   - `Some(PathBuf)` - If the global configuration item is found and its value is not empty,
  *     the function returns `Some` containing the LXC path.
- *   - `None`         - If the global configuration item is not found or its value is empty,
+ *   - `None`          - If the global configuration item is not found or its value is empty,
  *     the function returns `None`.
  */
-pub fn path() -> Option<String> {
-    get_global_config_item("lxc.lxcpath")
+pub fn path() -> Option<std::path::PathBuf> {
+    get_global_config_item_os("lxc.lxcpath")
+        .ok()
+        .flatten()
+        .map(Into::into)
 }
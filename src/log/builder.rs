@@ -0,0 +1,148 @@
+use super::{Level, Log};
+use std::path::{Path, PathBuf};
+
+/**
+ * Builder for [`Log`].
+ *
+ * Every field is optional: the LXC path defaults to [`crate::path()`], the
+ * level to [`Level::Error`] and the remaining fields are left to liblxc
+ * defaults.
+ */
+pub struct Builder {
+    name: Option<String>,
+    lxcpath: Option<PathBuf>,
+    file: Option<PathBuf>,
+    level: Level,
+    prefix: Option<String>,
+    quiet: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    /**
+     * Create a new builder with default values.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            lxcpath: crate::path(),
+            file: None,
+            level: Level::default(),
+            prefix: None,
+            quiet: false,
+        }
+    }
+
+    /**
+     * Set the container name used in log lines.
+     */
+    #[must_use]
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /**
+     * Set the LXC path used to compute the default log file.
+     */
+    #[must_use]
+    pub fn lxcpath(mut self, lxcpath: impl AsRef<Path>) -> Self {
+        self.lxcpath = Some(lxcpath.as_ref().to_path_buf());
+        self
+    }
+
+    /**
+     * Set the log file.
+     */
+    #[must_use]
+    pub fn file(mut self, file: impl AsRef<Path>) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+
+    /**
+     * Set the log level.
+     */
+    #[must_use]
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /**
+     * Set the prefix of log lines.
+     */
+    #[must_use]
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /**
+     * Don't log to stderr.
+     */
+    #[must_use]
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /**
+     * Build the log configuration.
+     */
+    #[must_use]
+    pub fn build(self) -> Log {
+        Log {
+            name: self.name,
+            lxcpath: self.lxcpath,
+            file: self.file,
+            level: self.level,
+            prefix: self.prefix,
+            quiet: self.quiet,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Builder, Level};
+    use std::path::Path;
+
+    #[test]
+    fn default() {
+        let log = Builder::default().build();
+
+        assert_eq!(log.lxcpath, crate::path());
+        assert_eq!(log.lxcpath, Builder::new().build().lxcpath);
+        assert_eq!(log.name, None);
+        assert_eq!(log.file, None);
+        assert_eq!(log.level, Level::Error);
+        assert_eq!(log.prefix, None);
+        assert!(!log.quiet);
+    }
+
+    #[test]
+    fn build() {
+        let log = Builder::new()
+            .name("c1")
+            .lxcpath("/var/lib/lxc")
+            .file("/tmp/lxc.log")
+            .level(Level::Debug)
+            .prefix("test")
+            .quiet(true)
+            .build();
+
+        assert_eq!(log.name.as_deref(), Some("c1"));
+        assert_eq!(log.lxcpath.as_deref(), Some(Path::new("/var/lib/lxc")));
+        assert_eq!(log.file.as_deref(), Some(Path::new("/tmp/lxc.log")));
+        assert_eq!(log.level, Level::Debug);
+        assert_eq!(log.prefix.as_deref(), Some("test"));
+        assert!(log.quiet);
+    }
+}
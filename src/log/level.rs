@@ -1,9 +1,11 @@
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum Level {
     Trace,
     Debug,
     Info,
     Notice,
     Warn,
+    #[default]
     Error,
     Crit,
     Alert,
@@ -1,38 +1,36 @@
+mod builder;
 mod level;
 
+pub use builder::Builder;
 pub use level::Level;
 
+use std::path::PathBuf;
+
 pub struct Log {
-    pub name: String,
-    pub lxcpath: String,
-    pub file: String,
+    pub name: Option<String>,
+    pub lxcpath: Option<PathBuf>,
+    pub file: Option<PathBuf>,
     pub level: Level,
-    pub prefix: String,
+    pub prefix: Option<String>,
     pub quiet: bool,
 }
 
-#[cfg(feature = "v2_1")]
-impl From<Log> for lxc_sys::lxc_log {
-    fn from(value: Log) -> Self {
-        let level: String = value.level.into();
-
-        Self {
-            name: cstr!(&value.name),
-            lxcpath: cstr!(&value.lxcpath),
-            file: cstr!(&value.file),
-            level: cstr!(&level),
-            prefix: cstr!(&value.prefix),
-            quiet: value.quiet,
-        }
+impl Log {
+    /**
+     * Create a log builder.
+     */
+    #[must_use]
+    pub fn builder() -> Builder {
+        Builder::new()
     }
-}
 
-impl Log {
     /**
      * Initialize the log.
      */
     pub fn init(self) -> crate::Result {
-        match self.log_init() {
+        crate::load()?;
+
+        match self.log_init()? {
             0 => Ok(()),
             num => Err(crate::Error {
                 num,
@@ -41,25 +39,91 @@ impl Log {
         }
     }
 
+    /**
+     * Initialize the log and close it when the returned guard is dropped.
+     */
+    pub fn init_guarded(self) -> crate::Result<Guard> {
+        self.init()?;
+
+        Ok(Guard { _private: () })
+    }
+
     #[cfg(not(feature = "v2_1"))]
-    fn log_init(self) -> i32 {
-        -1
+    fn log_init(self) -> crate::Result<i32> {
+        Err(crate::Error::unsupported("lxc_log_init"))
     }
 
     #[cfg(feature = "v2_1")]
-    fn log_init(self) -> i32 {
-        let mut info: lxc_sys::lxc_log = self.into();
+    fn log_init(self) -> crate::Result<i32> {
+        use crate::ffi::{opt_ptr, to_cstr, to_cstr_opt};
+
+        let name = to_cstr_opt(self.name.as_deref())?;
+        let lxcpath = to_cstr_opt(self.lxcpath.as_deref())?;
+        let file = to_cstr_opt(self.file.as_deref())?;
+        let level = to_cstr(&String::from(self.level))?;
+        let prefix = to_cstr_opt(self.prefix.as_deref())?;
+
+        let mut info = lxc_sys::lxc_log {
+            name: opt_ptr(name.as_deref()),
+            lxcpath: opt_ptr(lxcpath.as_deref()),
+            file: opt_ptr(file.as_deref()),
+            level: level.as_ptr(),
+            prefix: opt_ptr(prefix.as_deref()),
+            quiet: self.quiet,
+        };
 
-        unsafe { lxc_sys::lxc_log_init(&mut info) }
+        Ok(unsafe { lxc_sys::lxc_log_init(&mut info) })
     }
 
     /**
      * Close log file.
      */
     pub fn close() {
-        #[cfg(feature = "v2_0")]
+        #[cfg(feature = "v2_1")]
         unsafe {
             lxc_sys::lxc_log_close()
         }
     }
 }
+
+/**
+ * Closes the log on drop.
+ */
+#[derive(Debug)]
+#[must_use = "the log is closed as soon as the guard is dropped"]
+pub struct Guard {
+    _private: (),
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        Log::close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Guard, Log};
+
+    #[test]
+    fn guard() {
+        // Closing a log never initialized is harmless, even without liblxc
+        let guard = Guard { _private: () };
+        drop(guard);
+    }
+
+    #[test]
+    fn init_guarded() {
+        if crate::load().is_ok() {
+            return;
+        }
+
+        let err = Log::builder()
+            .quiet(true)
+            .build()
+            .init_guarded()
+            .unwrap_err();
+
+        assert_eq!(err.num, -libc::ELIBACC);
+    }
+}
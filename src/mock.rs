@@ -0,0 +1,543 @@
+use crate::ContainerApi;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+/**
+ * In-memory container implementing [`crate::ContainerApi`].
+ *
+ * Lifecycle calls update the state like liblxc would (`start()` makes the
+ * container `RUNNING`, `freeze()` makes it `FROZEN`…). States, commands
+ * results and errors can be scripted to exercise other paths.
+ */
+#[derive(Debug)]
+pub struct MockContainer {
+    name: String,
+    inner: RefCell<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    defined: bool,
+    state: String,
+    states: VecDeque<String>,
+    init_pid: i32,
+    config: HashMap<String, String>,
+    ips: Vec<(String, std::net::IpAddr)>,
+    exec: HashMap<Vec<String>, i32>,
+    errors: HashMap<String, VecDeque<crate::Error>>,
+    reboots: HashMap<String, VecDeque<i32>>,
+    calls: Vec<String>,
+}
+
+impl MockContainer {
+    /**
+     * Create a defined and stopped container.
+     */
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            inner: RefCell::new(Inner {
+                defined: true,
+                state: "STOPPED".to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /**
+     * Container name.
+     */
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Set whether the container is defined.
+     */
+    #[must_use]
+    pub fn with_defined(self, defined: bool) -> Self {
+        self.inner.borrow_mut().defined = defined;
+        self
+    }
+
+    /**
+     * Set the current state.
+     */
+    #[must_use]
+    pub fn with_state(self, state: &str) -> Self {
+        self.set_state(state);
+        self
+    }
+
+    /**
+     * Queue states returned by successive `state()` calls.
+     *
+     * Only `state()` advances the queue, other methods see the current
+     * state. Once the queue is empty, the last state stays current.
+     */
+    #[must_use]
+    pub fn with_states(self, states: &[&str]) -> Self {
+        self.inner
+            .borrow_mut()
+            .states
+            .extend(states.iter().map(ToString::to_string));
+        self
+    }
+
+    /**
+     * Set a config item.
+     */
+    #[must_use]
+    pub fn with_config_item(self, key: &str, value: &str) -> Self {
+        self.inner
+            .borrow_mut()
+            .config
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /**
+     * Add an IP address to an interface.
+     */
+    #[must_use]
+    pub fn with_ip(self, interface: &str, ip: std::net::IpAddr) -> Self {
+        self.inner
+            .borrow_mut()
+            .ips
+            .push((interface.to_string(), ip));
+        self
+    }
+
+    /**
+     * Set the result of `attach_run_wait()` for this command line.
+     */
+    #[must_use]
+    pub fn with_exec(self, argv: &[&str], status: i32) -> Self {
+        self.inner
+            .borrow_mut()
+            .exec
+            .insert(argv.iter().map(ToString::to_string).collect(), status);
+        self
+    }
+
+    /**
+     * Make the next call to `method` fail with `error`.
+     *
+     * Calling it several times queues errors for the following calls.
+     */
+    #[must_use]
+    pub fn with_error(self, method: &str, error: crate::Error) -> Self {
+        self.inner
+            .borrow_mut()
+            .errors
+            .entry(method.to_string())
+            .or_default()
+            .push_back(error);
+        self
+    }
+
+    /**
+     * Set the pid of the init process, reported while the container runs.
+     */
+    #[must_use]
+    pub fn with_init_pid(self, init_pid: i32) -> Self {
+        self.inner.borrow_mut().init_pid = init_pid;
+        self
+    }
+
+    /**
+     * Make the next successful call to `method` reboot the running
+     * container instead of its usual effect: it keeps running, with
+     * `init_pid` as new init.
+     *
+     * Calling it several times queues reboots for the following calls.
+     */
+    #[must_use]
+    pub fn with_reboot(self, method: &str, init_pid: i32) -> Self {
+        self.inner
+            .borrow_mut()
+            .reboots
+            .entry(method.to_string())
+            .or_default()
+            .push_back(init_pid);
+        self
+    }
+
+    /**
+     * Change the current state.
+     */
+    pub fn set_state(&self, state: &str) {
+        let mut inner = self.inner.borrow_mut();
+
+        inner.states.clear();
+        inner.state = state.to_string();
+    }
+
+    /**
+     * Methods called so far, in order.
+     */
+    #[must_use]
+    pub fn calls(&self) -> Vec<String> {
+        self.inner.borrow().calls.clone()
+    }
+
+    fn call(&self, method: &str) -> crate::Result {
+        let mut inner = self.inner.borrow_mut();
+
+        inner.calls.push(method.to_string());
+
+        match inner.errors.get_mut(method).and_then(VecDeque::pop_front) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn current_state(&self) -> String {
+        self.inner.borrow().state.clone()
+    }
+
+    fn running(&self) -> bool {
+        matches!(self.current_state().as_str(), "RUNNING" | "FROZEN")
+    }
+
+    /**
+     * Apply a reboot queued for `method`, if any.
+     */
+    fn rebooted(&self, method: &str) -> bool {
+        let mut inner = self.inner.borrow_mut();
+
+        match inner.reboots.get_mut(method).and_then(VecDeque::pop_front) {
+            Some(init_pid) => {
+                inner.init_pid = init_pid;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn transition(&self, method: &str, from: &[&str], to: &str) -> crate::Result {
+        self.call(method)?;
+
+        let current = self.current_state();
+
+        if !from.contains(&current.as_str()) {
+            return Err(crate::Error {
+                num: -1,
+                str: format!("{method}: container {} is {current}", self.name),
+            });
+        }
+
+        if self.running() && self.rebooted(method) {
+            return Ok(());
+        }
+
+        self.set_state(to);
+
+        Ok(())
+    }
+}
+
+impl ContainerApi for MockContainer {
+    fn is_defined(&self) -> bool {
+        self.inner.borrow().defined
+    }
+
+    fn state(&self) -> Option<String> {
+        let mut inner = self.inner.borrow_mut();
+
+        if let Some(state) = inner.states.pop_front() {
+            inner.state = state;
+        }
+
+        Some(inner.state.clone())
+    }
+
+    fn is_running(&self) -> bool {
+        self.running()
+    }
+
+    fn init_pid(&self) -> i32 {
+        if self.running() {
+            self.inner.borrow().init_pid
+        } else {
+            -1
+        }
+    }
+
+    fn start(&self, _use_init: bool, _argv: &[&str]) -> crate::Result {
+        if !self.is_defined() {
+            self.call("start")?;
+
+            return Err(crate::Error {
+                num: -1,
+                str: format!("container {} is not defined", self.name),
+            });
+        }
+
+        self.transition("start", &["STOPPED"], "RUNNING")?;
+        self.inner.borrow_mut().init_pid = 1;
+
+        Ok(())
+    }
+
+    fn stop(&self) -> crate::Result {
+        self.transition("stop", &["RUNNING", "FROZEN"], "STOPPED")
+    }
+
+    fn shutdown(&self, _timeout: i32) -> crate::Result {
+        self.transition("shutdown", &["RUNNING"], "STOPPED")
+    }
+
+    fn reboot(&self) -> crate::Result {
+        self.transition("reboot", &["RUNNING"], "RUNNING")
+    }
+
+    /**
+     * Only `SIGKILL` stops the container, use `set_state()` for other
+     * effects.
+     */
+    fn signal(&self, signal: crate::Signal) -> crate::Result {
+        self.call("signal")?;
+
+        if !self.running() {
+            return Err(crate::Error {
+                num: -libc::ESRCH,
+                str: format!("container {} isn't running", self.name),
+            });
+        }
+
+        if self.rebooted("signal") {
+            return Ok(());
+        }
+
+        if signal == crate::Signal::KILL {
+            self.set_state("STOPPED");
+        }
+
+        Ok(())
+    }
+
+    fn freeze(&self) -> crate::Result {
+        self.transition("freeze", &["RUNNING"], "FROZEN")
+    }
+
+    fn unfreeze(&self) -> crate::Result {
+        self.transition("unfreeze", &["FROZEN"], "RUNNING")
+    }
+
+    fn wait(&self, state: &str, timeout: i32) -> crate::Result {
+        self.call("wait")?;
+
+        if self.current_state() == state {
+            Ok(())
+        } else {
+            Err(crate::Error {
+                num: -1,
+                str: format!("timeout after {timeout}s waiting for {state}"),
+            })
+        }
+    }
+
+    fn destroy(&self) -> crate::Result {
+        self.transition("destroy", &["STOPPED"], "STOPPED")?;
+
+        let mut inner = self.inner.borrow_mut();
+        inner.defined = false;
+        inner.config.clear();
+
+        Ok(())
+    }
+
+    fn get_config_item(&self, key: &str) -> crate::Result<Option<String>> {
+        self.call("get_config_item")?;
+
+        Ok(self.inner.borrow().config.get(key).cloned())
+    }
+
+    fn set_config_item(&self, key: &str, value: &str) -> crate::Result {
+        self.call("set_config_item")?;
+
+        self.inner
+            .borrow_mut()
+            .config
+            .insert(key.to_string(), value.to_string());
+
+        Ok(())
+    }
+
+    fn clear_config_item(&self, key: &str) -> crate::Result {
+        self.call("clear_config_item")?;
+
+        self.inner.borrow_mut().config.remove(key);
+
+        Ok(())
+    }
+
+    fn get_interfaces(&self) -> Vec<String> {
+        if self.call("get_interfaces").is_err() {
+            return Vec::new();
+        }
+
+        let mut interfaces = Vec::new();
+
+        for (interface, _) in &self.inner.borrow().ips {
+            if !interfaces.contains(interface) {
+                interfaces.push(interface.clone());
+            }
+        }
+
+        interfaces
+    }
+
+    fn get_ips(
+        &self,
+        interface: Option<&str>,
+        family: Option<&str>,
+        _scope: std::os::raw::c_int,
+    ) -> crate::Result<Vec<std::net::IpAddr>> {
+        self.call("get_ips")?;
+
+        if !self.running() {
+            return Ok(Vec::new());
+        }
+
+        let ips = self
+            .inner
+            .borrow()
+            .ips
+            .iter()
+            .filter(|(name, _)| interface.is_none_or(|x| x == name))
+            .filter(|(_, ip)| match family {
+                Some("inet") => ip.is_ipv4(),
+                Some("inet6") => ip.is_ipv6(),
+                _ => true,
+            })
+            .map(|(_, ip)| *ip)
+            .collect();
+
+        Ok(ips)
+    }
+
+    fn attach_run_wait(
+        &self,
+        _options: &mut crate::attach::Options,
+        program: &str,
+        argv: &[&str],
+    ) -> crate::Result<i32> {
+        self.call("attach_run_wait")?;
+
+        if !self.running() {
+            return Err(crate::Error {
+                num: -1,
+                str: format!("container {} is not running", self.name),
+            });
+        }
+
+        let command: &[&str] = if argv.is_empty() { &[program] } else { argv };
+        let command = command.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        self.inner
+            .borrow()
+            .exec
+            .get(&command)
+            .copied()
+            .ok_or_else(|| crate::Error {
+                num: -1,
+                str: format!("no result for command {command:?}"),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockContainer;
+    use crate::ContainerApi;
+
+    #[test]
+    fn states() {
+        let container = MockContainer::new("c1").with_states(&["STARTING", "RUNNING"]);
+
+        assert!(!container.is_running());
+        assert_eq!(container.init_pid(), -1);
+        assert!(container.wait("RUNNING", 0).is_err());
+
+        assert_eq!(container.state().as_deref(), Some("STARTING"));
+        assert_eq!(container.state().as_deref(), Some("RUNNING"));
+        assert_eq!(container.state().as_deref(), Some("RUNNING"));
+        assert!(container.is_running());
+        assert!(container.wait("RUNNING", 0).is_ok());
+
+        container.set_state("STOPPED");
+        assert_eq!(container.state().as_deref(), Some("STOPPED"));
+    }
+
+    #[test]
+    fn lifecycle() {
+        let container = MockContainer::new("c1");
+
+        container.start(false, &[]).unwrap();
+        assert_eq!(container.init_pid(), 1);
+        container.freeze().unwrap();
+        assert!(container.is_running());
+        assert!(container.shutdown(0).is_err());
+        container.unfreeze().unwrap();
+        container.stop().unwrap();
+        container.destroy().unwrap();
+
+        assert!(!container.is_defined());
+        assert!(container.start(false, &[]).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |str: &str| crate::Error {
+            num: -1,
+            str: str.to_string(),
+        };
+        let container = MockContainer::new("c1")
+            .with_error("start", error("first"))
+            .with_error("start", error("second"));
+
+        assert_eq!(container.start(false, &[]).unwrap_err().str, "first");
+        assert_eq!(container.start(false, &[]).unwrap_err().str, "second");
+        assert!(container.start(false, &[]).is_ok());
+        assert_eq!(container.calls(), ["start", "start", "start"]);
+        assert_eq!(container.state().as_deref(), Some("RUNNING"));
+    }
+
+    #[test]
+    fn exec() {
+        let container = MockContainer::new("c1")
+            .with_exec(&["true"], 0)
+            .with_exec(&["sh", "-c", "exit 3"], 3);
+        let mut options = crate::attach::options(crate::AttchFlags::DEFAULT.bits(), 0);
+
+        assert!(
+            container
+                .attach_run_wait(&mut options, "true", &[])
+                .is_err()
+        );
+
+        container.start(false, &[]).unwrap();
+
+        assert_eq!(
+            container
+                .attach_run_wait(&mut options, "true", &[])
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            container
+                .attach_run_wait(&mut options, "sh", &["sh", "-c", "exit 3"])
+                .unwrap(),
+            3
+        );
+        assert!(
+            container
+                .attach_run_wait(&mut options, "false", &[])
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use crate::MountFlags;
+use std::path::{Path, PathBuf};
+
+/**
+ * Mount of a host path in a container.
+ */
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub(crate) source: PathBuf,
+    pub(crate) target: PathBuf,
+    pub(crate) fstype: Option<String>,
+    pub(crate) flags: MountFlags,
+    pub(crate) data: Option<String>,
+}
+
+impl Options {
+    /**
+     * Mount `source` from the host onto `target` in the container.
+     */
+    #[must_use]
+    pub fn new(source: impl AsRef<Path>, target: impl AsRef<Path>) -> Self {
+        Self {
+            source: source.as_ref().to_path_buf(),
+            target: target.as_ref().to_path_buf(),
+            fstype: None,
+            flags: MountFlags::empty(),
+            data: None,
+        }
+    }
+
+    /**
+     * Bind mount `source`.
+     */
+    #[must_use]
+    pub fn bind(mut self) -> Self {
+        self.flags |= MountFlags::BIND;
+        self
+    }
+
+    #[must_use]
+    pub fn readonly(mut self) -> Self {
+        self.flags |= MountFlags::RDONLY;
+        self
+    }
+
+    /**
+     * Also bind the mounts below `source`.
+     */
+    #[must_use]
+    pub fn recursive(mut self) -> Self {
+        self.flags |= MountFlags::REC;
+        self
+    }
+
+    #[must_use]
+    pub fn fstype(mut self, fstype: &str) -> Self {
+        self.fstype = Some(fstype.to_string());
+        self
+    }
+
+    /**
+     * Filesystem specific options, e.g. `size=64m` for tmpfs.
+     */
+    #[must_use]
+    pub fn data(mut self, data: &str) -> Self {
+        self.data = Some(data.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn flags(mut self, flags: MountFlags) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /**
+     * `lxc.mount.entry` value of this mount.
+     */
+    #[must_use]
+    pub fn entry(&self) -> String {
+        let mut options = Vec::new();
+
+        if self.flags.contains(MountFlags::BIND) {
+            if self.flags.contains(MountFlags::REC) {
+                options.push("rbind");
+            } else {
+                options.push("bind");
+            }
+
+            if self.source.is_dir() {
+                options.push("create=dir");
+            } else {
+                options.push("create=file");
+            }
+        }
+
+        let names = [
+            (MountFlags::RDONLY, "ro"),
+            (MountFlags::NOSUID, "nosuid"),
+            (MountFlags::NODEV, "nodev"),
+            (MountFlags::NOEXEC, "noexec"),
+            (MountFlags::SYNCHRONOUS, "sync"),
+            (MountFlags::NOATIME, "noatime"),
+            (MountFlags::NODIRATIME, "nodiratime"),
+            (MountFlags::RELATIME, "relatime"),
+            (MountFlags::PRIVATE, "private"),
+            (MountFlags::SLAVE, "slave"),
+            (MountFlags::SHARED, "shared"),
+        ];
+
+        for (flag, name) in names {
+            if self.flags.contains(flag) {
+                options.push(name);
+            }
+        }
+
+        if let Some(data) = &self.data {
+            options.push(data);
+        }
+
+        if options.is_empty() {
+            options.push("defaults");
+        }
+
+        format!(
+            "{} {} {} {} 0 0",
+            escape(&self.source),
+            escape(self.target.strip_prefix("/").unwrap_or(&self.target)),
+            self.fstype.as_deref().unwrap_or("none"),
+            options.join(","),
+        )
+    }
+}
+
+/**
+ * Live mount, unmounted when dropped.
+ */
+#[cfg(feature = "v3_1")]
+#[must_use = "the mount is removed when the guard is dropped"]
+pub struct Guard<'a> {
+    container: &'a crate::Container,
+    target: PathBuf,
+    mounted: bool,
+}
+
+#[cfg(feature = "v3_1")]
+impl<'a> Guard<'a> {
+    pub(crate) fn new(container: &'a crate::Container, target: &Path) -> Self {
+        Self {
+            container,
+            target: target.to_path_buf(),
+            mounted: true,
+        }
+    }
+
+    /**
+     * Path of the mount in the container.
+     */
+    #[must_use]
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /**
+     * Unmount now, reporting errors.
+     */
+    pub fn unmount(mut self) -> crate::Result {
+        self.mounted = false;
+        self.container.umount(&self.target, 0)
+    }
+
+    /**
+     * Keep the mount after the guard is dropped.
+     */
+    pub fn leak(mut self) {
+        self.mounted = false;
+    }
+}
+
+#[cfg(feature = "v3_1")]
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        if self.mounted {
+            self.container.umount(&self.target, 0).ok();
+        }
+    }
+}
+
+impl crate::Container {
+    /**
+     * Add a mount to the container config, used at the next start.
+     */
+    pub fn mount_persistent(&self, options: &Options) -> crate::Result {
+        self.set_config_item("lxc.mount.entry", &options.entry())?;
+
+        match self.config_file_name() {
+            Some(config) => self.save_config(config),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "v3_1")]
+struct Remount {
+    target: std::ffi::CString,
+    flags: MountFlags,
+    propagation: MountFlags,
+}
+
+#[cfg(feature = "v3_1")]
+impl crate::Container {
+    /**
+     * Apply `flags` to the bind mount `target` from inside the container,
+     * since liblxc resolves the source of its mounts on the host.
+     */
+    pub(crate) fn remount(&self, target: &Path, flags: MountFlags) -> crate::Result {
+        use std::os::fd::AsRawFd;
+
+        let propagation = flags & (MountFlags::PRIVATE | MountFlags::SLAVE | MountFlags::SHARED);
+        let mut remount = Remount {
+            target: crate::ffi::to_cstr(target)?,
+            flags: (flags - propagation - MountFlags::REC) | MountFlags::BIND | MountFlags::REMOUNT,
+            propagation: if propagation.is_empty() {
+                propagation
+            } else {
+                propagation | (flags & MountFlags::REC)
+            },
+        };
+
+        let null = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/null")?;
+        // Keep the capabilities and the LSM profile of the caller to mount
+        let mut options = crate::attach::options(0, null.as_raw_fd());
+        let payload = unsafe { &mut *(&raw mut remount).cast::<std::os::raw::c_void>() };
+
+        let pid = self.attach(Some(remount_attached), payload, &mut options)?;
+
+        let mut status = 0;
+        if unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        match (libc::WIFEXITED(status), libc::WEXITSTATUS(status)) {
+            (true, 0) => Ok(()),
+            (true, errno) => Err(crate::Error {
+                num: -errno,
+                str: format!(
+                    "Unable to remount {}: {}",
+                    target.display(),
+                    std::io::Error::from_raw_os_error(errno)
+                ),
+            }),
+            (false, _) => Err(crate::Error {
+                num: -libc::ECHILD,
+                str: format!("Unable to remount {}", target.display()),
+            }),
+        }
+    }
+}
+
+/**
+ * Attached process remounting, exits with the `errno` of the failure.
+ */
+#[cfg(feature = "v3_1")]
+unsafe extern "C" fn remount_attached(payload: *mut std::os::raw::c_void) -> std::os::raw::c_int {
+    let remount = unsafe { &*payload.cast::<Remount>() };
+
+    let mount = |flags: MountFlags| unsafe {
+        libc::mount(
+            std::ptr::null(),
+            remount.target.as_ptr(),
+            std::ptr::null(),
+            flags.bits(),
+            std::ptr::null(),
+        ) == 0
+    };
+
+    if mount(remount.flags) && (remount.propagation.is_empty() || mount(remount.propagation)) {
+        0
+    } else {
+        std::io::Error::last_os_error()
+            .raw_os_error()
+            .unwrap_or(libc::EPERM)
+    }
+}
+
+fn escape(path: &Path) -> String {
+    path.display()
+        .to_string()
+        .replace('\\', "\\134")
+        .replace(' ', "\\040")
+        .replace('\t', "\\011")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Options;
+    use crate::MountFlags;
+
+    #[test]
+    fn entry() {
+        let dir = std::env::temp_dir();
+
+        assert_eq!(
+            Options::new(&dir, "/mnt/data").bind().readonly().entry(),
+            format!("{} mnt/data none bind,create=dir,ro 0 0", dir.display())
+        );
+        assert_eq!(
+            Options::new("/nonexistent/file", "etc/resolv.conf")
+                .bind()
+                .recursive()
+                .entry(),
+            "/nonexistent/file etc/resolv.conf none rbind,create=file 0 0"
+        );
+        assert_eq!(
+            Options::new("tmpfs", "/tmp")
+                .fstype("tmpfs")
+                .flags(MountFlags::NOSUID | MountFlags::NODEV)
+                .data("size=64m")
+                .entry(),
+            "tmpfs tmp tmpfs nosuid,nodev,size=64m 0 0"
+        );
+        assert_eq!(
+            Options::new("proc", "proc").fstype("proc").entry(),
+            "proc proc proc defaults 0 0"
+        );
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            Options::new("/nonexistent/my dir\t\\x", "/mnt/my dir").entry(),
+            "/nonexistent/my\\040dir\\011\\134x mnt/my\\040dir none defaults 0 0"
+        );
+    }
+}
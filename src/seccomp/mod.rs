@@ -0,0 +1,5 @@
+mod notifier;
+mod profile;
+
+pub use notifier::{Notification, Notifier, Response};
+pub use profile::{Action, Arg, Cmp, Mode, Profile, Rule, Section};
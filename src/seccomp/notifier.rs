@@ -0,0 +1,340 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::path::PathBuf;
+
+/**
+ * Syscall intercepted by a `notify` seccomp rule, see [`Notifier`].
+ */
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub id: u64,
+    /** Pid of the calling process, in the host pid namespace. */
+    pub pid: u32,
+    /** Syscall number, e.g. `libc::SYS_mknod`. */
+    pub syscall: i32,
+    /** `AUDIT_ARCH_*` value of the syscall. */
+    pub arch: u32,
+    pub instruction_pointer: u64,
+    pub args: [u64; 6],
+}
+
+/**
+ * Reply to a [`Notification`].
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Response {
+    /** Fail the syscall with this errno. */
+    Errno(i32),
+    /** Succeed without running the syscall, returning this value. */
+    Value(i64),
+    /** Let the kernel run the syscall. */
+    Continue,
+}
+
+impl Response {
+    fn encode(self, id: u64) -> libc::seccomp_notif_resp {
+        let mut resp = libc::seccomp_notif_resp {
+            id,
+            val: 0,
+            error: 0,
+            flags: 0,
+        };
+
+        match self {
+            Self::Errno(errno) => resp.error = -errno.abs(),
+            Self::Value(val) => resp.val = val,
+            Self::Continue => resp.flags = libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32,
+        }
+
+        resp
+    }
+}
+
+/**
+ * Listener on a seccomp notification fd.
+ */
+#[derive(Debug)]
+pub struct Notifier {
+    fd: OwnedFd,
+    size: usize,
+}
+
+impl Notifier {
+    /**
+     * Listen on a seccomp notification fd.
+     */
+    pub fn new(fd: OwnedFd) -> crate::Result<Self> {
+        let mut sizes = libc::seccomp_notif_sizes {
+            seccomp_notif: 0,
+            seccomp_notif_resp: 0,
+            seccomp_data: 0,
+        };
+
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_GET_NOTIF_SIZES,
+                0,
+                &mut sizes as *mut libc::seccomp_notif_sizes,
+            )
+        };
+
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(Self {
+            fd,
+            size: notif_size(&sizes),
+        })
+    }
+
+    /**
+     * Listen to the notifications of a container.
+     *
+     * The container must have a `lxc.seccomp.notify.proxy` or a seccomp
+     * profile with `notify` rules.
+     */
+    #[cfg(feature = "v3_2")]
+    pub fn from_container(container: &crate::Container) -> crate::Result<Self> {
+        #[cfg(feature = "v5_0")]
+        let fd = container.seccomp_notify_fd_active()?;
+        #[cfg(not(feature = "v5_0"))]
+        let fd = container.seccomp_notify_fd()?;
+
+        Self::new(fd)
+    }
+
+    /**
+     * Wait for the next notification.
+     */
+    pub fn recv(&self) -> crate::Result<Notification> {
+        let mut buffer = notif_buffer(self.size);
+
+        let ret = unsafe {
+            libc::ioctl(
+                self.fd.as_raw_fd(),
+                libc::SECCOMP_IOCTL_NOTIF_RECV,
+                buffer.as_mut_ptr(),
+            )
+        };
+
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        let notif = unsafe { &*buffer.as_ptr().cast::<libc::seccomp_notif>() };
+
+        Ok(Notification {
+            id: notif.id,
+            pid: notif.pid,
+            syscall: notif.data.nr,
+            arch: notif.data.arch,
+            instruction_pointer: notif.data.instruction_pointer,
+            args: notif.data.args,
+        })
+    }
+
+    /**
+     * Reply to a notification.
+     */
+    pub fn respond(&self, notification: &Notification, response: Response) -> crate::Result {
+        let mut resp = response.encode(notification.id);
+
+        let ret = unsafe {
+            libc::ioctl(
+                self.fd.as_raw_fd(),
+                libc::SECCOMP_IOCTL_NOTIF_SEND,
+                &mut resp as *mut libc::seccomp_notif_resp,
+            )
+        };
+
+        if ret < 0 {
+            Err(std::io::Error::last_os_error().into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /**
+     * Whether the process behind a notification still waits for a reply.
+     */
+    #[must_use]
+    pub fn is_valid(&self, notification: &Notification) -> bool {
+        let mut id = notification.id;
+
+        unsafe {
+            libc::ioctl(
+                self.fd.as_raw_fd(),
+                libc::SECCOMP_IOCTL_NOTIF_ID_VALID,
+                &mut id as *mut u64,
+            ) == 0
+        }
+    }
+
+    /**
+     * Read `len` bytes at `addr` in the memory of the calling process, e.g.
+     * a syscall buffer argument.
+     */
+    pub fn read_memory(
+        &self,
+        notification: &Notification,
+        addr: u64,
+        len: usize,
+    ) -> crate::Result<Vec<u8>> {
+        use std::os::unix::fs::FileExt;
+
+        let mem = std::fs::File::open(format!("/proc/{}/mem", notification.pid))?;
+
+        // The pid may have been reused since the notification was received
+        if !self.is_valid(notification) {
+            return Err(crate::Error {
+                num: -libc::ENOENT,
+                str: format!("notification {} is gone", notification.id),
+            });
+        }
+
+        let mut buffer = vec![0; len];
+        let n = mem.read_at(&mut buffer, addr)?;
+        buffer.truncate(n);
+
+        Ok(buffer)
+    }
+
+    /**
+     * Read a path argument of the calling process, e.g. `mknod` first one.
+     */
+    pub fn read_path(&self, notification: &Notification, addr: u64) -> crate::Result<PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+
+        let mut path = self.read_memory(notification, addr, libc::PATH_MAX as usize)?;
+
+        match path.iter().position(|x| *x == 0) {
+            Some(len) => path.truncate(len),
+            None => {
+                return Err(crate::Error {
+                    num: -libc::ENAMETOOLONG,
+                    str: "path argument isn't terminated".to_string(),
+                });
+            }
+        }
+
+        Ok(std::ffi::OsString::from_vec(path).into())
+    }
+
+    /**
+     * Answer notifications with `handler` until the filter has no more
+     * users, i.e. the container stopped.
+     */
+    pub fn run(&self, mut handler: impl FnMut(&Self, &Notification) -> Response) -> crate::Result {
+        loop {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            if unsafe { libc::poll(&mut pollfd, 1, -1) } < 0 {
+                let error = std::io::Error::last_os_error();
+
+                if error.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+
+                return Err(error.into());
+            }
+
+            if pollfd.revents & libc::POLLIN == 0 {
+                return Ok(());
+            }
+
+            let notification = match self.recv() {
+                Ok(notification) => notification,
+                // The calling process died or a signal interrupted us
+                Err(err) if err.num == -libc::ENOENT || err.num == -libc::EINTR => continue,
+                Err(err) => return Err(err),
+            };
+
+            let response = handler(self, &notification);
+
+            match self.respond(&notification, response) {
+                Err(err) if err.num != -libc::ENOENT => return Err(err),
+                _ => (),
+            }
+        }
+    }
+}
+
+impl AsFd for Notifier {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+/**
+ * Size of the buffer receiving a notification, the kernel may know a bigger
+ * struct than libc.
+ */
+fn notif_size(sizes: &libc::seccomp_notif_sizes) -> usize {
+    usize::from(sizes.seccomp_notif).max(size_of::<libc::seccomp_notif>())
+}
+
+fn notif_buffer(size: usize) -> Vec<u64> {
+    // u64 keeps the buffer aligned for `seccomp_notif`
+    vec![0; size.div_ceil(size_of::<u64>())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Notifier, Response};
+
+    #[test]
+    fn encode() {
+        let resp = Response::Errno(libc::EPERM).encode(42);
+        assert_eq!(
+            (resp.id, resp.error, resp.val, resp.flags),
+            (42, -libc::EPERM, 0, 0)
+        );
+
+        let resp = Response::Errno(-libc::ENOSYS).encode(42);
+        assert_eq!(resp.error, -libc::ENOSYS);
+
+        let resp = Response::Value(-1).encode(43);
+        assert_eq!((resp.id, resp.error, resp.val, resp.flags), (43, 0, -1, 0));
+
+        let resp = Response::Continue.encode(44);
+        assert_eq!((resp.error, resp.val), (0, 0));
+        assert_eq!(resp.flags, libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32);
+    }
+
+    #[test]
+    fn notif_size() {
+        let libc_size = size_of::<libc::seccomp_notif>();
+        let sizes = |seccomp_notif| libc::seccomp_notif_sizes {
+            seccomp_notif,
+            seccomp_notif_resp: 24,
+            seccomp_data: 64,
+        };
+
+        assert_eq!(super::notif_size(&sizes(0)), libc_size);
+        assert_eq!(
+            super::notif_size(&sizes(libc_size as u16 + 12)),
+            libc_size + 12
+        );
+
+        let buffer = super::notif_buffer(libc_size + 12);
+        assert!(size_of_val(buffer.as_slice()) >= libc_size + 12);
+        assert_eq!(size_of_val(buffer.as_slice()) % size_of::<u64>(), 0);
+    }
+
+    #[test]
+    fn new() {
+        let fd = std::fs::File::open("/dev/null").unwrap().into();
+
+        // The kernel may lack seccomp, SECCOMP_GET_NOTIF_SIZES needs 5.0
+        let Ok(notifier) = Notifier::new(fd) else {
+            return;
+        };
+
+        assert!(notifier.size >= size_of::<libc::seccomp_notif>());
+    }
+}
@@ -0,0 +1,564 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "v2_1")]
+const KEY: &str = "lxc.seccomp.profile";
+#[cfg(not(feature = "v2_1"))]
+const KEY: &str = "lxc.seccomp";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /** Syscalls are denied unless a rule allows them. */
+    Allowlist,
+    /** Syscalls are allowed unless a rule denies them. */
+    Denylist,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    Kill,
+    Allow,
+    Trap,
+    Log,
+    /** Fail the syscall with this errno. */
+    Errno(u32),
+    /** Forward the syscall to a [`super::Notifier`]. */
+    Notify,
+}
+
+/**
+ * Comparison of a syscall argument.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cmp {
+    Ne,
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+    /** The argument masked by this value equals the compared one. */
+    MaskedEq(u64),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Arg {
+    pub index: u32,
+    pub cmp: Cmp,
+    pub value: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rule {
+    pub syscall: String,
+    /** Action of the rule, the list default one if `None`. */
+    pub action: Option<Action>,
+    pub args: Vec<Arg>,
+}
+
+/**
+ * Rules for an architecture, or for all if `arch` is `None`.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Section {
+    pub arch: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+/**
+ * Seccomp policy in the LXC version 2 format, see `lxc.container.conf(5)`.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Profile {
+    pub mode: Mode,
+    pub default_action: Option<Action>,
+    pub sections: Vec<Section>,
+}
+
+impl Action {
+    fn parse(words: &[&str]) -> Option<(Self, usize)> {
+        let action = match *words.first()? {
+            "kill" => Self::Kill,
+            "allow" => Self::Allow,
+            "trap" => Self::Trap,
+            "log" => Self::Log,
+            "notify" => Self::Notify,
+            "errno" => return Some((Self::Errno(words.get(1)?.parse().ok()?), 2)),
+            _ => return None,
+        };
+
+        Some((action, 1))
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Kill => f.write_str("kill"),
+            Self::Allow => f.write_str("allow"),
+            Self::Trap => f.write_str("trap"),
+            Self::Log => f.write_str("log"),
+            Self::Errno(errno) => write!(f, "errno {errno}"),
+            Self::Notify => f.write_str("notify"),
+        }
+    }
+}
+
+impl Arg {
+    fn parse(s: &str) -> Option<Self> {
+        let fields = s
+            .strip_prefix('[')?
+            .strip_suffix(']')?
+            .split(',')
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        let (index, value, cmp) = match fields.as_slice() {
+            [index, value, cmp] | [index, value, cmp, _] => (index, value, *cmp),
+            _ => return None,
+        };
+
+        let cmp = match cmp {
+            "SCMP_CMP_NE" | "!=" => Cmp::Ne,
+            "SCMP_CMP_LT" | "<" => Cmp::Lt,
+            "SCMP_CMP_LE" | "<=" => Cmp::Le,
+            "SCMP_CMP_EQ" | "==" => Cmp::Eq,
+            "SCMP_CMP_GE" | ">=" => Cmp::Ge,
+            "SCMP_CMP_GT" | ">" => Cmp::Gt,
+            "SCMP_CMP_MASKED_EQ" | "&=" => Cmp::MaskedEq(parse_u64(fields.get(3)?)?),
+            _ => return None,
+        };
+
+        Some(Self {
+            index: parse_u64(index)?.try_into().ok()?,
+            cmp,
+            value: parse_u64(value)?,
+        })
+    }
+}
+
+impl std::fmt::Display for Arg {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let cmp = match self.cmp {
+            Cmp::Ne => "SCMP_CMP_NE",
+            Cmp::Lt => "SCMP_CMP_LT",
+            Cmp::Le => "SCMP_CMP_LE",
+            Cmp::Eq => "SCMP_CMP_EQ",
+            Cmp::Ge => "SCMP_CMP_GE",
+            Cmp::Gt => "SCMP_CMP_GT",
+            Cmp::MaskedEq(mask) => {
+                return write!(
+                    f,
+                    "[{},{},SCMP_CMP_MASKED_EQ,{mask}]",
+                    self.index, self.value
+                );
+            }
+        };
+
+        write!(f, "[{},{},{cmp}]", self.index, self.value)
+    }
+}
+
+impl Rule {
+    /**
+     * Create a rule applying `action` to `syscall`.
+     */
+    #[must_use]
+    pub fn new(syscall: &str, action: Action) -> Self {
+        Self {
+            syscall: syscall.to_string(),
+            action: Some(action),
+            args: Vec::new(),
+        }
+    }
+
+    /**
+     * Only match calls whose argument `index` compares to `value`.
+     */
+    #[must_use]
+    pub fn arg(mut self, index: u32, cmp: Cmp, value: u64) -> Self {
+        self.args.push(Arg { index, cmp, value });
+        self
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (syscall, words) = words.split_first()?;
+
+        let (action, skip) = match Action::parse(words) {
+            Some((action, skip)) => (Some(action), skip),
+            None => (None, 0),
+        };
+
+        let args = words[skip..]
+            .iter()
+            .map(|x| Arg::parse(x))
+            .collect::<Option<Vec<_>>>()?;
+
+        if args.len() > 6 {
+            return None;
+        }
+
+        Some(Self {
+            syscall: syscall.to_string(),
+            action,
+            args,
+        })
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.syscall)?;
+
+        if let Some(action) = &self.action {
+            write!(f, " {action}")?;
+        }
+
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Profile {
+    /**
+     * Create a profile denying every syscall not allowed by a rule.
+     */
+    #[must_use]
+    pub fn allowlist() -> Self {
+        Self::new(Mode::Allowlist)
+    }
+
+    /**
+     * Create a profile allowing every syscall not denied by a rule.
+     */
+    #[must_use]
+    pub fn denylist() -> Self {
+        Self::new(Mode::Denylist)
+    }
+
+    fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            default_action: None,
+            sections: vec![Section {
+                arch: None,
+                rules: Vec::new(),
+            }],
+        }
+    }
+
+    /**
+     * Action of syscalls matching no rule.
+     */
+    #[must_use]
+    pub fn default_action(mut self, action: Action) -> Self {
+        self.default_action = Some(action);
+        self
+    }
+
+    /**
+     * Add the next rules to the `arch` section, e.g. `x86_64` or `all`.
+     */
+    #[must_use]
+    pub fn arch(mut self, arch: &str) -> Self {
+        self.sections.push(Section {
+            arch: Some(arch.to_string()),
+            rules: Vec::new(),
+        });
+        self
+    }
+
+    /**
+     * Add a rule to the current section.
+     */
+    #[must_use]
+    pub fn rule(mut self, rule: Rule) -> Self {
+        if let Some(section) = self.sections.last_mut() {
+            section.rules.push(rule);
+        }
+        self
+    }
+
+    /**
+     * Parse a version 2 profile.
+     *
+     * Version 1 profiles, lists of syscall numbers, are refused: LXC's version
+     * 2 parser skips rules naming a syscall by number.
+     */
+    pub fn parse(s: &str) -> crate::Result<Self> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(x, line)| (x + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let version = lines.next().map(|(_, line)| line);
+
+        if version == Some("1") {
+            return Err(crate::Error {
+                num: crate::Error::INVALID,
+                str: "version 1 seccomp profiles aren't supported, name the syscalls in a version 2 one".to_string(),
+            });
+        }
+
+        if version != Some("2") {
+            return Err(crate::Error {
+                num: crate::Error::INVALID,
+                str: "unsupported seccomp profile version".to_string(),
+            });
+        }
+
+        let (n, line) = lines.next().ok_or_else(|| invalid(2, ""))?;
+        let words = line.split_whitespace().collect::<Vec<_>>();
+
+        let mut profile = match words.first() {
+            Some(&"allowlist" | &"whitelist") => Self::allowlist(),
+            Some(&"denylist" | &"blacklist") => Self::denylist(),
+            _ => return Err(invalid(n, line)),
+        };
+
+        if words.len() > 1 {
+            match Action::parse(&words[1..]) {
+                Some((action, skip)) if skip + 1 == words.len() => {
+                    profile.default_action = Some(action);
+                }
+                _ => return Err(invalid(n, line)),
+            }
+        }
+
+        for (n, line) in lines {
+            if let Some(arch) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                profile = profile.arch(arch);
+            } else {
+                profile = profile.rule(Rule::parse(line).ok_or_else(|| invalid(n, line))?);
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /**
+     * Render the profile in the LXC version 2 format.
+     */
+    #[must_use]
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+
+    /**
+     * Write the profile next to the container config and use it at the next
+     * start.
+     */
+    pub fn install(&self, container: &crate::Container) -> crate::Result<PathBuf> {
+        let config = container.config_file_name().ok_or_else(|| crate::Error {
+            num: -libc::ENOENT,
+            str: "container has no config file".to_string(),
+        })?;
+        let path = config.with_file_name("seccomp.profile");
+
+        std::fs::write(&path, self.render())?;
+
+        container.set_config_item(KEY, &path.to_string_lossy())?;
+        container.save_config(config)?;
+
+        Ok(path)
+    }
+}
+
+impl std::str::FromStr for Profile {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "2")?;
+
+        match self.mode {
+            Mode::Allowlist => f.write_str("allowlist")?,
+            Mode::Denylist => f.write_str("denylist")?,
+        }
+
+        if let Some(action) = &self.default_action {
+            write!(f, " {action}")?;
+        }
+
+        writeln!(f)?;
+
+        for section in &self.sections {
+            if let Some(arch) = &section.arch {
+                writeln!(f, "[{arch}]")?;
+            }
+
+            for rule in &section.rules {
+                writeln!(f, "{rule}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Parse a number like `lxc_safe_uint64()` in base 0: `0x` prefixed numbers
+ * are hexadecimal, other `0` prefixed ones octal.
+ */
+fn parse_u64(s: &str) -> Option<u64> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(octal) = s.strip_prefix('0').filter(|x| !x.is_empty()) {
+        u64::from_str_radix(octal, 8).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+fn invalid(line: usize, content: &str) -> crate::Error {
+    crate::Error {
+        num: crate::Error::INVALID,
+        str: format!("invalid seccomp profile line {line}: {content}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Arg, Cmp, Mode, Profile, Rule};
+
+    #[test]
+    fn parse() {
+        let profile = Profile::parse(
+            "2
+# comment
+denylist errno 1
+
+[all]
+kexec_load errno 1
+open_by_handle_at
+[x86_64]
+personality kill [0,0x8,SCMP_CMP_NE]
+ioctl notify [1,21522,SCMP_CMP_MASKED_EQ,0xffff] [02,0xffffffffffffffff,>=]
+",
+        )
+        .unwrap();
+
+        assert_eq!(profile.mode, Mode::Denylist);
+        assert_eq!(profile.default_action, Some(Action::Errno(1)));
+        assert_eq!(profile.sections.len(), 3);
+        assert!(profile.sections[0].rules.is_empty());
+        assert_eq!(profile.sections[1].arch.as_deref(), Some("all"));
+        assert_eq!(
+            profile.sections[1].rules,
+            [
+                Rule::new("kexec_load", Action::Errno(1)),
+                Rule {
+                    syscall: "open_by_handle_at".to_string(),
+                    action: None,
+                    args: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(
+            profile.sections[2].rules,
+            [
+                Rule::new("personality", Action::Kill).arg(0, Cmp::Ne, 8),
+                Rule::new("ioctl", Action::Notify)
+                    .arg(1, Cmp::MaskedEq(0xffff), 0x5412)
+                    .arg(2, Cmp::Ge, u64::MAX),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_v1() {
+        let err = Profile::parse("1\n0\n# comment\n60\n").unwrap_err();
+
+        assert_eq!(err.num, crate::Error::INVALID);
+        assert!(err.str.contains("version 1"));
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for (s, line) in [
+            ("2\n", "line 2"),
+            ("2\ngraylist\n", "line 2"),
+            ("2\nallowlist errno\n", "line 2"),
+            ("2\nallowlist kill kill\n", "line 2"),
+            ("2\nallowlist\nread [0,1]\n", "line 3"),
+            ("2\nallowlist\nread [0,1,SCMP_CMP_FOO]\n", "line 3"),
+            ("2\nallowlist\nread [a,1,SCMP_CMP_EQ]\n", "line 3"),
+            ("2\nallowlist\nread [0,1,SCMP_CMP_MASKED_EQ]\n", "line 3"),
+            (
+                "2\nallowlist\nread [0,1,==] [1,1,==] [2,1,==] [3,1,==] [4,1,==] [5,1,==] [6,1,==]\n",
+                "line 3",
+            ),
+        ] {
+            let err = Profile::parse(s).unwrap_err();
+
+            assert_eq!(err.num, crate::Error::INVALID, "{s}");
+            assert!(err.str.contains(line), "{s}: {}", err.str);
+        }
+
+        assert!(Profile::parse("").is_err());
+        assert!(Profile::parse("3\nallowlist\n").is_err());
+    }
+
+    #[test]
+    fn render() {
+        let profile = Profile::allowlist()
+            .default_action(Action::Kill)
+            .rule(Rule::new("read", Action::Allow))
+            .arch("x86_64")
+            .rule(Rule::new("ioctl", Action::Errno(38)).arg(1, Cmp::MaskedEq(255), 1))
+            .rule(Rule::new("kill", Action::Log).arg(1, Cmp::Eq, 9));
+
+        let rendered = profile.render();
+
+        assert_eq!(
+            rendered,
+            "2
+allowlist kill
+read allow
+[x86_64]
+ioctl errno 38 [1,1,SCMP_CMP_MASKED_EQ,255]
+kill log [1,9,SCMP_CMP_EQ]
+"
+        );
+        assert_eq!(rendered.parse::<Profile>().unwrap(), profile);
+    }
+
+    #[test]
+    fn arg() {
+        assert_eq!(
+            Arg::parse("[ 2 , 4 , <= ]"),
+            Some(Arg {
+                index: 2,
+                cmp: Cmp::Le,
+                value: 4,
+            })
+        );
+        assert_eq!(Arg::parse("2,4,<="), None);
+    }
+
+    #[test]
+    fn base() {
+        assert_eq!(
+            Arg::parse("[0x1,010,SCMP_CMP_MASKED_EQ,0XfF]"),
+            Some(Arg {
+                index: 1,
+                cmp: Cmp::MaskedEq(255),
+                value: 8,
+            })
+        );
+        assert_eq!(Arg::parse("[0,0,==]").map(|x| x.value), Some(0));
+        assert_eq!(Arg::parse("[0,08,==]"), None);
+        assert_eq!(Arg::parse("[0,-1,==]"), None);
+        assert_eq!(Arg::parse("[0,0x,==]"), None);
+        assert_eq!(Arg::parse("[0x100000000,0,==]"), None);
+    }
+}
@@ -0,0 +1,86 @@
+/**
+ * Signal sent to a container init, see `Container::signal()`.
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Signal(pub i32);
+
+impl Signal {
+    pub const HUP: Self = Self(libc::SIGHUP);
+    pub const INT: Self = Self(libc::SIGINT);
+    pub const QUIT: Self = Self(libc::SIGQUIT);
+    pub const KILL: Self = Self(libc::SIGKILL);
+    pub const USR1: Self = Self(libc::SIGUSR1);
+    pub const USR2: Self = Self(libc::SIGUSR2);
+    pub const TERM: Self = Self(libc::SIGTERM);
+    pub const CONT: Self = Self(libc::SIGCONT);
+    pub const STOP: Self = Self(libc::SIGSTOP);
+    /** Power failure, used by some inits to halt. */
+    pub const PWR: Self = Self(libc::SIGPWR);
+
+    /**
+     * Real-time signal `SIGRTMIN + n`, e.g. `SIGRTMIN+3` halts systemd.
+     */
+    #[must_use]
+    pub fn realtime(n: i32) -> Self {
+        Self(libc::SIGRTMIN() + n)
+    }
+}
+
+impl From<i32> for Signal {
+    fn from(signal: i32) -> Self {
+        Self(signal)
+    }
+}
+
+impl crate::Container {
+    /**
+     * Send a signal to the container init.
+     *
+     * The init pidfd is used when available, so the signal can't reach
+     * another process reusing the pid.
+     */
+    pub fn signal(&self, signal: Signal) -> crate::Result {
+        #[cfg(feature = "v4_0")]
+        if crate::Capabilities::detect().is_ok_and(|x| x.pidfd) {
+            use std::os::fd::AsRawFd;
+
+            // Falls back to the pid if the kernel lacks pidfd support
+            if let Ok(pidfd) = self.init_pidfd() {
+                let ret = unsafe {
+                    libc::syscall(
+                        libc::SYS_pidfd_send_signal,
+                        pidfd.as_raw_fd(),
+                        signal.0,
+                        std::ptr::null::<libc::siginfo_t>(),
+                        0,
+                    )
+                };
+
+                if ret == 0 {
+                    return Ok(());
+                }
+
+                let error = std::io::Error::last_os_error();
+
+                if error.raw_os_error() != Some(libc::ENOSYS) {
+                    return Err(error.into());
+                }
+            }
+        }
+
+        let pid = self.init_pid();
+
+        if pid <= 0 {
+            return Err(crate::Error {
+                num: -libc::ESRCH,
+                str: "container isn't running".to_string(),
+            });
+        }
+
+        if unsafe { libc::kill(pid, signal.0) } < 0 {
+            Err(std::io::Error::last_os_error().into())
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,371 @@
+mod retention;
+
+pub use retention::{Plan, RetentionPolicy};
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/**
+ * Name of a snapshot, `snap<n>` for snapshots created by liblxc.
+ */
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Name(String);
+
+impl Name {
+    pub(crate) fn from_number(number: i32) -> Self {
+        Self(format!("snap{number}"))
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /**
+     * Snapshot number, if the name follows the `snap<n>` scheme.
+     */
+    #[must_use]
+    pub fn number(&self) -> Option<u32> {
+        self.0.strip_prefix("snap")?.parse().ok()
+    }
+}
+
+impl From<&str> for Name {
+    fn from(name: &str) -> Self {
+        Self(name.to_string())
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/**
+ * Snapshot of a container, see `lxc::Container::snapshot_list()`.
+ */
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub name: Name,
+    /** File holding the snapshot comment. */
+    pub comment_pathname: Option<PathBuf>,
+    /** Creation time, formatted as `%Y:%m:%d %H:%M:%S`. */
+    pub timestamp: String,
+    /** Directory containing the snapshot. */
+    pub lxcpath: PathBuf,
+}
+
+impl Snapshot {
+    pub(crate) fn from_raw(raw: &lxc_sys::lxc_snapshot) -> Self {
+        let comment_pathname = if raw.comment_pathname.is_null() {
+            None
+        } else {
+            Some(crate::ffi::to_os_string(raw.comment_pathname).into())
+        };
+
+        Self {
+            name: crate::ffi::to_string_lossy(raw.name).as_str().into(),
+            comment_pathname,
+            timestamp: crate::ffi::to_string_lossy(raw.timestamp),
+            lxcpath: crate::ffi::to_os_string(raw.lxcpath).into(),
+        }
+    }
+
+    /**
+     * Read the snapshot comment.
+     */
+    pub fn comment(&self) -> crate::Result<Option<String>> {
+        match &self.comment_pathname {
+            Some(path) if path.exists() => Ok(Some(std::fs::read_to_string(path)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /**
+     * Creation time, `None` if the timestamp can't be parsed.
+     */
+    #[must_use]
+    pub fn time(&self) -> Option<std::time::SystemTime> {
+        let date = Date::parse(&self.timestamp)?;
+
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        tm.tm_year = date.year - 1900;
+        tm.tm_mon = date.month as i32 - 1;
+        tm.tm_mday = date.day as i32;
+        tm.tm_hour = date.hour as i32;
+        tm.tm_min = date.minute as i32;
+        tm.tm_sec = date.second as i32;
+        tm.tm_isdst = -1;
+
+        let secs = unsafe { libc::mktime(&mut tm) };
+
+        if secs < 0 {
+            None
+        } else {
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs as u64))
+        }
+    }
+
+    /**
+     * Directory of the snapshot container.
+     */
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        self.lxcpath.join(self.name.as_str())
+    }
+
+    /**
+     * Root filesystem of the snapshot.
+     *
+     * Only directory backed snapshots can be browsed.
+     */
+    pub fn rootfs(&self) -> crate::Result<PathBuf> {
+        let config = std::fs::read_to_string(self.path().join("config"))?;
+
+        let rootfs = config.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+
+            match key.trim() {
+                "lxc.rootfs.path" | "lxc.rootfs" => Some(value.trim()),
+                _ => None,
+            }
+        });
+
+        match rootfs {
+            Some(rootfs) if rootfs.starts_with('/') => Ok(rootfs.into()),
+            Some(rootfs) if rootfs.starts_with("dir:") => Ok(rootfs["dir:".len()..].into()),
+            Some(rootfs) => Err(crate::Error {
+                num: crate::Error::UNSUPPORTED,
+                str: format!("Unable to browse rootfs {rootfs} of snapshot {}", self.name),
+            }),
+            None => Ok(self.path().join("rootfs")),
+        }
+    }
+}
+
+/**
+ * Local date and time of a snapshot timestamp.
+ */
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+impl Date {
+    fn parse(timestamp: &str) -> Option<Self> {
+        let (date, time) = timestamp.trim().split_once(' ')?;
+
+        let mut date = date.splitn(3, ':');
+        let mut time = time.splitn(3, ':');
+
+        let date = Self {
+            year: date.next()?.parse().ok()?,
+            month: date.next()?.parse().ok()?,
+            day: date.next()?.parse().ok()?,
+            hour: time.next()?.parse().ok()?,
+            minute: time.next()?.parse().ok()?,
+            second: time.next()?.parse().ok()?,
+        };
+
+        if (1..=12).contains(&date.month) && (1..=31).contains(&date.day) {
+            Some(date)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Days since 1970-01-01.
+     */
+    fn days(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year - era * 400;
+        let month = i64::from(self.month);
+        let doy =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146_097 + doe - 719_468
+    }
+
+    /**
+     * Weeks since the monday before 1970-01-01.
+     */
+    fn weeks(&self) -> i64 {
+        (self.days() + 3).div_euclid(7)
+    }
+}
+
+/**
+ * Files differing between two snapshots, relative to their root filesystem.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Diff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /** Files whose type, mode, owner, size, content or link target changed. */
+    pub changed: Vec<PathBuf>,
+}
+
+impl Diff {
+    /**
+     * Compare two root filesystem trees.
+     */
+    pub fn between(a: &Path, b: &Path) -> crate::Result<Self> {
+        let before = walk(a)?;
+        let after = walk(b)?;
+
+        let mut diff = Self::default();
+
+        for (path, metadata) in &before {
+            match after.get(path) {
+                None => diff.removed.push(path.clone()),
+                Some(other) if !same_file(&a.join(path), metadata, &b.join(path), other)? => {
+                    diff.changed.push(path.clone());
+                }
+                Some(_) => (),
+            }
+        }
+
+        diff.added = after
+            .into_keys()
+            .filter(|path| !before.contains_key(path))
+            .collect();
+
+        Ok(diff)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn walk(root: &Path) -> crate::Result<BTreeMap<PathBuf, std::fs::Metadata>> {
+    let mut entries = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = std::fs::symlink_metadata(&path)?;
+
+            if metadata.is_dir() {
+                dirs.push(path.clone());
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            entries.insert(relative, metadata);
+        }
+    }
+
+    Ok(entries)
+}
+
+fn same_file(
+    a: &Path,
+    a_metadata: &std::fs::Metadata,
+    b: &Path,
+    b_metadata: &std::fs::Metadata,
+) -> crate::Result<bool> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    if a_metadata.file_type() != b_metadata.file_type()
+        || a_metadata.mode() != b_metadata.mode()
+        || a_metadata.uid() != b_metadata.uid()
+        || a_metadata.gid() != b_metadata.gid()
+    {
+        return Ok(false);
+    }
+
+    if a_metadata.is_symlink() {
+        return Ok(std::fs::read_link(a)? == std::fs::read_link(b)?);
+    }
+
+    if a_metadata.file_type().is_block_device() || a_metadata.file_type().is_char_device() {
+        return Ok(a_metadata.rdev() == b_metadata.rdev());
+    }
+
+    if !a_metadata.is_file() {
+        return Ok(true);
+    }
+
+    if a_metadata.len() != b_metadata.len() {
+        return Ok(false);
+    }
+
+    same_content(a, b)
+}
+
+fn same_content(a: &Path, b: &Path) -> crate::Result<bool> {
+    use std::io::Read;
+
+    let mut a = std::io::BufReader::new(std::fs::File::open(a)?);
+    let mut b = std::io::BufReader::new(std::fs::File::open(b)?);
+
+    let mut a_buffer = [0; 8192];
+    let mut b_buffer = [0; 8192];
+
+    loop {
+        let size = a.read(&mut a_buffer)?;
+
+        if size == 0 {
+            return Ok(b.read(&mut b_buffer)? == 0);
+        }
+
+        b.read_exact(&mut b_buffer[..size])?;
+
+        if a_buffer[..size] != b_buffer[..size] {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Date;
+
+    #[test]
+    fn parse() {
+        let date = Date::parse("2024:02:29 13:45:01").unwrap();
+
+        assert_eq!((date.year, date.month, date.day), (2024, 2, 29));
+        assert_eq!((date.hour, date.minute, date.second), (13, 45, 1));
+
+        assert_eq!(Date::parse("2024:13:01 00:00:00"), None);
+        assert_eq!(Date::parse("2024:01:00 00:00:00"), None);
+        assert_eq!(Date::parse("2024-01-01 00:00:00"), None);
+        assert_eq!(Date::parse("2024:01:01"), None);
+        assert_eq!(Date::parse(""), None);
+    }
+
+    #[test]
+    fn days() {
+        let days = |timestamp| Date::parse(timestamp).unwrap().days();
+
+        assert_eq!(days("1970:01:01 00:00:00"), 0);
+        assert_eq!(days("1969:12:31 23:59:59"), -1);
+        assert_eq!(days("2000:03:01 00:00:00"), 11_017);
+        assert_eq!(days("2024:03:01 00:00:00") - days("2024:02:28 00:00:00"), 2);
+        assert_eq!(days("2023:03:01 00:00:00") - days("2023:02:28 00:00:00"), 1);
+    }
+
+    #[test]
+    fn weeks() {
+        let weeks = |timestamp| Date::parse(timestamp).unwrap().weeks();
+
+        // 1970-01-01 is a thursday
+        assert_eq!(weeks("1970:01:04 00:00:00"), 0);
+        assert_eq!(weeks("1970:01:05 00:00:00"), 1);
+        assert_eq!(weeks("1969:12:29 00:00:00"), 0);
+        assert_eq!(weeks("1969:12:28 00:00:00"), -1);
+    }
+}
@@ -0,0 +1,292 @@
+use super::{Date, Snapshot};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+/**
+ * Which snapshots to keep when pruning.
+ *
+ * A snapshot is kept if any `keep_*` rule selects it and it isn't older than
+ * the maximum age. Without `keep_*` rules, every snapshot younger than the
+ * maximum age is kept. Snapshots with an unreadable timestamp are always
+ * kept.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    last: Option<usize>,
+    daily: Option<usize>,
+    weekly: Option<usize>,
+    max_age: Option<Duration>,
+}
+
+/**
+ * Result of a [`RetentionPolicy`], newest snapshots first.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Plan {
+    pub keep: Vec<Snapshot>,
+    pub destroy: Vec<Snapshot>,
+}
+
+impl RetentionPolicy {
+    /**
+     * Create a policy keeping every snapshot.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Keep the `count` most recent snapshots.
+     */
+    #[must_use]
+    pub fn keep_last(mut self, count: usize) -> Self {
+        self.last = Some(count);
+        self
+    }
+
+    /**
+     * Keep the most recent snapshot of each of the last `count` days having
+     * snapshots.
+     */
+    #[must_use]
+    pub fn keep_daily(mut self, count: usize) -> Self {
+        self.daily = Some(count);
+        self
+    }
+
+    /**
+     * Keep the most recent snapshot of each of the last `count` weeks having
+     * snapshots.
+     */
+    #[must_use]
+    pub fn keep_weekly(mut self, count: usize) -> Self {
+        self.weekly = Some(count);
+        self
+    }
+
+    /**
+     * Destroy snapshots older than `max_age`, whatever the other rules.
+     */
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /**
+     * Compute which snapshots to keep and destroy.
+     */
+    #[must_use]
+    pub fn plan(&self, snapshots: &[Snapshot]) -> Plan {
+        self.plan_at(snapshots, SystemTime::now())
+    }
+
+    /**
+     * Compute which snapshots to keep and destroy at the given time.
+     */
+    #[must_use]
+    pub fn plan_at(&self, snapshots: &[Snapshot], now: SystemTime) -> Plan {
+        let mut snapshots = snapshots
+            .iter()
+            .map(|snapshot| (Date::parse(&snapshot.timestamp), snapshot))
+            .collect::<Vec<_>>();
+
+        snapshots.sort_by(|(a_date, a), (b_date, b)| {
+            b_date
+                .cmp(a_date)
+                .then_with(|| b.name.number().cmp(&a.name.number()))
+        });
+
+        let dated = snapshots
+            .iter()
+            .enumerate()
+            .filter_map(|(x, (date, _))| date.map(|date| (x, date)))
+            .collect::<Vec<_>>();
+
+        let mut selected = HashSet::new();
+
+        if let Some(last) = self.last {
+            selected.extend(dated.iter().take(last).map(|(x, _)| *x));
+        }
+
+        if let Some(daily) = self.daily {
+            selected.extend(Self::latest_per(&dated, daily, Date::days));
+        }
+
+        if let Some(weekly) = self.weekly {
+            selected.extend(Self::latest_per(&dated, weekly, Date::weeks));
+        }
+
+        let has_rules = self.last.is_some() || self.daily.is_some() || self.weekly.is_some();
+        let mut plan = Plan::default();
+
+        for (x, (date, snapshot)) in snapshots.into_iter().enumerate() {
+            let keep = match date {
+                None => true,
+                Some(_) => (!has_rules || selected.contains(&x)) && !self.too_old(snapshot, now),
+            };
+
+            if keep {
+                plan.keep.push(snapshot.clone());
+            } else {
+                plan.destroy.push(snapshot.clone());
+            }
+        }
+
+        plan
+    }
+
+    /**
+     * Destroy the container snapshots selected by the policy.
+     *
+     * With `dry_run`, nothing is destroyed and the plan is only returned.
+     */
+    pub fn apply(&self, container: &crate::Container, dry_run: bool) -> crate::Result<Plan> {
+        let plan = self.plan(&container.snapshot_list()?);
+
+        if !dry_run {
+            for snapshot in &plan.destroy {
+                container.snapshot_destroy(snapshot.name.as_str())?;
+            }
+        }
+
+        Ok(plan)
+    }
+
+    fn latest_per(dated: &[(usize, Date)], count: usize, bucket: fn(&Date) -> i64) -> Vec<usize> {
+        let mut buckets = Vec::new();
+        let mut selected = Vec::new();
+
+        for (x, date) in dated {
+            if buckets.len() == count {
+                break;
+            }
+
+            let bucket = bucket(date);
+
+            if !buckets.contains(&bucket) {
+                buckets.push(bucket);
+                selected.push(*x);
+            }
+        }
+
+        selected
+    }
+
+    fn too_old(&self, snapshot: &Snapshot, now: SystemTime) -> bool {
+        let Some(max_age) = self.max_age else {
+            return false;
+        };
+
+        snapshot
+            .time()
+            .and_then(|time| now.duration_since(time).ok())
+            .is_some_and(|age| age > max_age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetentionPolicy;
+    use crate::Snapshot;
+    use std::time::Duration;
+
+    fn snapshot(number: i32, timestamp: &str) -> Snapshot {
+        Snapshot {
+            name: crate::SnapshotName::from_number(number),
+            comment_pathname: None,
+            timestamp: timestamp.to_string(),
+            lxcpath: "/var/lib/lxc".into(),
+        }
+    }
+
+    fn names(snapshots: &[Snapshot]) -> Vec<String> {
+        snapshots.iter().map(|x| x.name.to_string()).collect()
+    }
+
+    fn snapshots() -> Vec<Snapshot> {
+        vec![
+            snapshot(0, "2024:01:01 10:00:00"),
+            snapshot(1, "2024:01:01 18:00:00"),
+            snapshot(2, "2024:01:02 10:00:00"),
+            snapshot(3, "2024:01:09 10:00:00"),
+            snapshot(4, "2024:01:10 10:00:00"),
+            snapshot(5, "2024:01:10 12:00:00"),
+        ]
+    }
+
+    #[test]
+    fn keep_everything() {
+        let snapshots = snapshots();
+        let plan = RetentionPolicy::new().plan_at(&snapshots, snapshots[5].time().unwrap());
+
+        assert_eq!(
+            names(&plan.keep),
+            ["snap5", "snap4", "snap3", "snap2", "snap1", "snap0"]
+        );
+        assert!(plan.destroy.is_empty());
+    }
+
+    #[test]
+    fn keep_last() {
+        let snapshots = snapshots();
+        let plan = RetentionPolicy::new()
+            .keep_last(2)
+            .plan_at(&snapshots, snapshots[5].time().unwrap());
+
+        assert_eq!(names(&plan.keep), ["snap5", "snap4"]);
+        assert_eq!(names(&plan.destroy), ["snap3", "snap2", "snap1", "snap0"]);
+    }
+
+    #[test]
+    fn keep_daily() {
+        let snapshots = snapshots();
+        let plan = RetentionPolicy::new()
+            .keep_daily(3)
+            .plan_at(&snapshots, snapshots[5].time().unwrap());
+
+        assert_eq!(names(&plan.keep), ["snap5", "snap3", "snap2"]);
+        assert_eq!(names(&plan.destroy), ["snap4", "snap1", "snap0"]);
+    }
+
+    #[test]
+    fn keep_weekly() {
+        let snapshots = snapshots();
+        let plan = RetentionPolicy::new()
+            .keep_weekly(5)
+            .plan_at(&snapshots, snapshots[5].time().unwrap());
+
+        // 2024-01-08 is a monday
+        assert_eq!(names(&plan.keep), ["snap5", "snap2"]);
+        assert_eq!(names(&plan.destroy), ["snap4", "snap3", "snap1", "snap0"]);
+    }
+
+    #[test]
+    fn max_age() {
+        let snapshots = snapshots();
+        let plan = RetentionPolicy::new()
+            .keep_last(5)
+            .max_age(Duration::from_secs(3 * 24 * 3600))
+            .plan_at(&snapshots, snapshots[5].time().unwrap());
+
+        assert_eq!(names(&plan.keep), ["snap5", "snap4", "snap3"]);
+        assert_eq!(names(&plan.destroy), ["snap2", "snap1", "snap0"]);
+    }
+
+    #[test]
+    fn unreadable_timestamp() {
+        let mut snapshots = snapshots();
+        snapshots.push(snapshot(6, "yesterday"));
+
+        let plan = RetentionPolicy::new()
+            .keep_last(1)
+            .plan_at(&snapshots, snapshots[5].time().unwrap());
+
+        assert_eq!(names(&plan.keep), ["snap5", "snap6"]);
+        assert_eq!(
+            names(&plan.destroy),
+            ["snap4", "snap3", "snap2", "snap1", "snap0"]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{ContainerApi, Signal};
+use std::time::Duration;
+
+/**
+ * Way of stopping a container, see [`Policy`].
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Step {
+    /** Ask the init to halt, like `lxc-stop`, and wait. */
+    Shutdown(Duration),
+    /** Send a signal to the init and wait. */
+    Signal(Signal, Duration),
+    /** Kill every process of the container and wait. */
+    Stop(Duration),
+}
+
+/**
+ * Steps tried in order by [`crate::Container::stop_gracefully()`].
+ *
+ * The default policy shuts down within 30 seconds, then sends `SIGTERM` and
+ * waits 10 seconds, then stops the container and waits 10 seconds.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Policy {
+    steps: Vec<Step>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self::new()
+            .shutdown(Duration::from_secs(30))
+            .signal(Signal::TERM, Duration::from_secs(10))
+            .stop(Duration::from_secs(10))
+    }
+}
+
+impl Policy {
+    /**
+     * Create an empty policy.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    #[must_use]
+    pub fn shutdown(mut self, timeout: Duration) -> Self {
+        self.steps.push(Step::Shutdown(timeout));
+        self
+    }
+
+    #[must_use]
+    pub fn signal(mut self, signal: Signal, timeout: Duration) -> Self {
+        self.steps.push(Step::Signal(signal, timeout));
+        self
+    }
+
+    #[must_use]
+    pub fn stop(mut self, timeout: Duration) -> Self {
+        self.steps.push(Step::Stop(timeout));
+        self
+    }
+
+    #[must_use]
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+impl crate::Container {
+    /**
+     * Try each step of `policy` until the container stops.
+     *
+     * A step whose request is refused counts as failed and the next one is
+     * tried: an init ignoring the halt signal, or rebooting instead of
+     * halting, which is noticed by its pid changing. Returns the step which
+     * stopped the container, or `None` if it wasn't running.
+     *
+     * There is no reboot step: `reboot2()` restarts the container, it never
+     * stops it.
+     */
+    pub fn stop_gracefully(&self, policy: &Policy) -> crate::Result<Option<Step>> {
+        stop_gracefully(self, policy)
+    }
+}
+
+pub(crate) fn stop_gracefully(
+    container: &impl ContainerApi,
+    policy: &Policy,
+) -> crate::Result<Option<Step>> {
+    if !container.is_running() {
+        return Ok(None);
+    }
+
+    let mut last_error = None;
+
+    for step in &policy.steps {
+        let init_pid = container.init_pid();
+
+        let result = match step {
+            Step::Shutdown(timeout) => container.shutdown(seconds(*timeout)),
+            Step::Signal(signal, timeout) => container
+                .signal(*signal)
+                .and_then(|()| container.wait("STOPPED", seconds(*timeout))),
+            Step::Stop(timeout) => container
+                .stop()
+                .and_then(|()| container.wait("STOPPED", seconds(*timeout))),
+        };
+
+        if !container.is_running() {
+            return Ok(Some(*step));
+        }
+
+        match result {
+            Err(err) => last_error = Some(err),
+            Ok(()) if container.init_pid() != init_pid => {
+                last_error = Some(crate::Error {
+                    num: -libc::EAGAIN,
+                    str: "container rebooted instead of stopping".to_string(),
+                });
+            }
+            Ok(()) => (),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| crate::Error {
+        num: -libc::EBUSY,
+        str: "container is still running".to_string(),
+    }))
+}
+
+fn seconds(duration: Duration) -> i32 {
+    i32::try_from(duration.as_secs()).unwrap_or(i32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Policy, Step};
+    use crate::{ContainerApi, MockContainer, Signal};
+    use std::time::Duration;
+
+    #[test]
+    fn stopped() {
+        let container = MockContainer::new("c1");
+
+        assert_eq!(container.stop_gracefully(&Policy::default()).unwrap(), None);
+        assert!(container.calls().is_empty());
+    }
+
+    #[test]
+    fn shutdown() {
+        let container = MockContainer::new("c1").with_state("RUNNING");
+        let step = container.stop_gracefully(&Policy::default()).unwrap();
+
+        assert_eq!(step, Some(Step::Shutdown(Duration::from_secs(30))));
+        assert_eq!(container.calls(), ["shutdown"]);
+    }
+
+    #[test]
+    fn escalate() {
+        let refused = crate::Error {
+            num: -1,
+            str: "refused".to_string(),
+        };
+        let container = MockContainer::new("c1")
+            .with_state("RUNNING")
+            .with_error("shutdown", refused);
+
+        let policy = Policy::new()
+            .shutdown(Duration::ZERO)
+            .signal(Signal::TERM, Duration::ZERO)
+            .signal(Signal::KILL, Duration::ZERO)
+            .stop(Duration::ZERO);
+
+        assert_eq!(
+            container.stop_gracefully(&policy).unwrap(),
+            Some(Step::Signal(Signal::KILL, Duration::ZERO))
+        );
+        assert_eq!(
+            container.calls(),
+            ["shutdown", "signal", "wait", "signal", "wait"]
+        );
+    }
+
+    #[test]
+    fn still_running() {
+        let container = MockContainer::new("c1").with_state("RUNNING").with_error(
+            "stop",
+            crate::Error {
+                num: -1,
+                str: "stuck".to_string(),
+            },
+        );
+
+        let err = container
+            .stop_gracefully(
+                &Policy::new()
+                    .signal(Signal::TERM, Duration::ZERO)
+                    .stop(Duration::ZERO),
+            )
+            .unwrap_err();
+
+        assert_eq!(err.str, "stuck");
+        assert!(container.is_running());
+
+        let err = container.stop_gracefully(&Policy::new()).unwrap_err();
+
+        assert_eq!(err.num, -libc::EBUSY);
+    }
+
+    #[test]
+    fn reboot() {
+        let container = MockContainer::new("c1")
+            .with_state("RUNNING")
+            .with_init_pid(100)
+            .with_reboot("shutdown", 101);
+
+        let policy = Policy::new()
+            .shutdown(Duration::ZERO)
+            .signal(Signal::KILL, Duration::ZERO);
+
+        assert_eq!(
+            container.stop_gracefully(&policy).unwrap(),
+            Some(Step::Signal(Signal::KILL, Duration::ZERO))
+        );
+
+        let container = MockContainer::new("c1")
+            .with_state("RUNNING")
+            .with_init_pid(100)
+            .with_reboot("shutdown", 101);
+
+        let err = container
+            .stop_gracefully(&Policy::new().shutdown(Duration::ZERO))
+            .unwrap_err();
+
+        assert_eq!(err.num, -libc::EAGAIN);
+        assert!(container.is_running());
+        assert_eq!(container.init_pid(), 101);
+    }
+}
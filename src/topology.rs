@@ -0,0 +1,368 @@
+use crate::ContainerApi;
+use std::time::{Duration, Instant};
+
+/**
+ * Condition for a container to be considered ready.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Readiness {
+    /** The container is `RUNNING`. */
+    Running,
+    /** An IP is assigned, on the given interface or any. */
+    Ip(Option<String>),
+    /** This command, run with `attach_run_wait()`, exits successfully. */
+    Command(Vec<String>),
+}
+
+/**
+ * Container of a [`Topology`].
+ */
+pub struct Service<'a, C: ContainerApi = crate::Container> {
+    name: String,
+    container: &'a C,
+    depends_on: Vec<String>,
+    readiness: Vec<Readiness>,
+}
+
+impl<'a, C: ContainerApi> Service<'a, C> {
+    /**
+     * Create a service ready once running.
+     */
+    #[must_use]
+    pub fn new(name: &str, container: &'a C) -> Self {
+        Self {
+            name: name.to_string(),
+            container,
+            depends_on: Vec::new(),
+            readiness: Vec::new(),
+        }
+    }
+
+    /**
+     * Start this service once `name` is ready.
+     */
+    #[must_use]
+    pub fn depends_on(mut self, name: &str) -> Self {
+        self.depends_on.push(name.to_string());
+        self
+    }
+
+    /**
+     * Add a readiness check, all of them must pass.
+     */
+    #[must_use]
+    pub fn ready_when(mut self, readiness: Readiness) -> Self {
+        self.readiness.push(readiness);
+        self
+    }
+
+    fn is_ready(&self) -> bool {
+        if !self.container.is_running() {
+            return false;
+        }
+
+        self.readiness.iter().all(|readiness| match readiness {
+            Readiness::Running => self.container.state().as_deref() == Some("RUNNING"),
+            Readiness::Ip(interface) => self
+                .container
+                .get_ips(interface.as_deref(), None, 0)
+                .is_ok_and(|ips| !ips.is_empty()),
+            Readiness::Command(command) => run(self.container, command),
+        })
+    }
+}
+
+/**
+ * Containers started in dependency order.
+ */
+pub struct Topology<'a, C: ContainerApi = crate::Container> {
+    services: Vec<Service<'a, C>>,
+    timeout: Duration,
+    interval: Duration,
+}
+
+impl<C: ContainerApi> Default for Topology<'_, C> {
+    fn default() -> Self {
+        Self {
+            services: Vec::new(),
+            timeout: Duration::from_secs(60),
+            interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl<'a, C: ContainerApi> Topology<'a, C> {
+    /**
+     * Create an empty topology, waiting up to 60 seconds for each service.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn service(mut self, service: Service<'a, C>) -> Self {
+        self.services.push(service);
+        self
+    }
+
+    /**
+     * Time to wait for a service to be ready, or stopped by `down()`.
+     */
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /**
+     * Delay between readiness checks.
+     */
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /**
+     * Service names in start order, dependencies first.
+     */
+    pub fn order(&self) -> crate::Result<Vec<&str>> {
+        Ok(self
+            .sorted()?
+            .into_iter()
+            .map(|service| service.name.as_str())
+            .collect())
+    }
+
+    fn sorted(&self) -> crate::Result<Vec<&Service<'a, C>>> {
+        let mut order: Vec<usize> = Vec::new();
+
+        for service in &self.services {
+            for dependency in &service.depends_on {
+                if !self.services.iter().any(|x| &x.name == dependency) {
+                    return Err(crate::Error {
+                        num: crate::Error::INVALID,
+                        str: format!("{} depends on unknown {dependency}", service.name),
+                    });
+                }
+            }
+        }
+
+        while order.len() < self.services.len() {
+            let next = self.services.iter().enumerate().position(|(x, service)| {
+                !order.contains(&x)
+                    && service.depends_on.iter().all(|dependency| {
+                        order.iter().any(|y| &self.services[*y].name == dependency)
+                    })
+            });
+
+            match next {
+                Some(x) => order.push(x),
+                None => {
+                    let names = self
+                        .services
+                        .iter()
+                        .enumerate()
+                        .filter(|(x, _)| !order.contains(x))
+                        .map(|(_, service)| service.name.as_str())
+                        .collect::<Vec<_>>();
+
+                    return Err(crate::Error {
+                        num: crate::Error::INVALID,
+                        str: format!("dependency cycle between {}", names.join(", ")),
+                    });
+                }
+            }
+        }
+
+        Ok(order.into_iter().map(|x| &self.services[x]).collect())
+    }
+
+    /**
+     * Start the services in order, waiting for each one to be ready before
+     * starting the ones depending on it.
+     */
+    pub fn up(&self) -> crate::Result {
+        for service in self.sorted()? {
+            let name = &service.name;
+
+            if !service.container.is_running() {
+                service.container.start(false, &[])?;
+            }
+
+            let deadline = Instant::now() + self.timeout;
+
+            while !service.is_ready() {
+                if Instant::now() >= deadline {
+                    return Err(crate::Error {
+                        num: -libc::ETIMEDOUT,
+                        str: format!("{name} isn't ready after {:?}", self.timeout),
+                    });
+                }
+
+                std::thread::sleep(self.interval);
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Stop the services in reverse order, dependents first.
+     *
+     * Each service is shut down, then stopped if it doesn't halt in time.
+     * Every service is tried, the first error is returned.
+     */
+    pub fn down(&self) -> crate::Result {
+        let mut result = Ok(());
+
+        for service in self.sorted()?.into_iter().rev() {
+            let container = service.container;
+
+            if !container.is_running() {
+                continue;
+            }
+
+            let timeout = i32::try_from(self.timeout.as_secs()).unwrap_or(i32::MAX);
+
+            if container.shutdown(timeout).is_err()
+                && let Err(err) = container.stop()
+                && result.is_ok()
+            {
+                result = Err(err);
+            }
+        }
+
+        result
+    }
+}
+
+fn run(container: &impl ContainerApi, command: &[String]) -> bool {
+    use std::os::fd::AsRawFd;
+
+    let Some(program) = command.first() else {
+        return true;
+    };
+    let Ok(null) = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")
+    else {
+        return false;
+    };
+
+    let mut options = crate::attach::options(crate::AttchFlags::DEFAULT.bits(), null.as_raw_fd());
+
+    let argv = command.iter().map(String::as_str).collect::<Vec<_>>();
+
+    container
+        .attach_run_wait(&mut options, program, &argv)
+        .is_ok_and(|status| status == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Readiness, Service, Topology};
+    use crate::{ContainerApi, MockContainer};
+    use std::time::Duration;
+
+    #[test]
+    fn order() {
+        let db = MockContainer::new("db");
+        let cache = MockContainer::new("cache");
+        let app = MockContainer::new("app");
+        let proxy = MockContainer::new("proxy");
+
+        let topology = Topology::new()
+            .service(Service::new("proxy", &proxy).depends_on("app"))
+            .service(
+                Service::new("app", &app)
+                    .depends_on("db")
+                    .depends_on("cache"),
+            )
+            .service(Service::new("db", &db))
+            .service(Service::new("cache", &cache));
+
+        assert_eq!(topology.order().unwrap(), ["db", "cache", "app", "proxy"]);
+        assert!(Topology::<MockContainer>::new().order().unwrap().is_empty());
+    }
+
+    #[test]
+    fn cycle() {
+        let a = MockContainer::new("a");
+        let b = MockContainer::new("b");
+        let c = MockContainer::new("c");
+
+        let topology = Topology::new()
+            .service(Service::new("c", &c))
+            .service(Service::new("a", &a).depends_on("b"))
+            .service(Service::new("b", &b).depends_on("a").depends_on("c"));
+
+        let err = topology.order().unwrap_err();
+
+        assert_eq!(err.num, crate::Error::INVALID);
+        assert_eq!(err.str, "dependency cycle between a, b");
+
+        let topology = Topology::new().service(Service::new("a", &a).depends_on("a"));
+
+        assert_eq!(
+            topology.order().unwrap_err().str,
+            "dependency cycle between a"
+        );
+    }
+
+    #[test]
+    fn unknown_dependency() {
+        let a = MockContainer::new("a");
+        let topology = Topology::new().service(Service::new("a", &a).depends_on("b"));
+        let err = topology.order().unwrap_err();
+
+        assert_eq!(err.num, crate::Error::INVALID);
+        assert_eq!(err.str, "a depends on unknown b");
+    }
+
+    #[test]
+    fn up_down() {
+        let db = MockContainer::new("db").with_exec(&["pg_isready"], 0);
+        let app = MockContainer::new("app");
+
+        let topology = Topology::new()
+            .service(Service::new("app", &app).depends_on("db"))
+            .service(
+                Service::new("db", &db)
+                    .ready_when(Readiness::Command(vec!["pg_isready".to_string()])),
+            );
+
+        topology.up().unwrap();
+
+        assert!(db.is_running());
+        assert!(app.is_running());
+
+        topology.down().unwrap();
+
+        assert!(!db.is_running());
+        assert!(!app.is_running());
+    }
+
+    #[test]
+    fn not_ready() {
+        let db = MockContainer::new("db").with_exec(&["pg_isready"], 1);
+        let app = MockContainer::new("app");
+
+        let topology = Topology::new()
+            .timeout(Duration::ZERO)
+            .interval(Duration::ZERO)
+            .service(Service::new("app", &app).depends_on("db"))
+            .service(
+                Service::new("db", &db)
+                    .ready_when(Readiness::Command(vec!["pg_isready".to_string()])),
+            );
+
+        let err = topology.up().unwrap_err();
+
+        assert_eq!(err.num, -libc::ETIMEDOUT);
+        assert!(db.is_running());
+        assert!(!app.is_running());
+    }
+}
@@ -0,0 +1,221 @@
+/**
+ * LXC version, as returned by [`crate::version()`].
+ *
+ * Distribution suffixes like `~git2209-g5a7b9ce67` or `-devel` are kept in
+ * `extra` and ignored by [`Version::at_least`].
+ *
+ * Versions are ordered by `major.minor.micro`, a version with a suffix being
+ * a development one sorting before the release, then by suffix.
+ */
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+    pub extra: Option<String>,
+}
+
+impl Version {
+    /**
+     * Determine if this version is greater or equal to `major.minor`.
+     */
+    #[must_use]
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.major, self.minor, self.micro)
+            .cmp(&(other.major, other.minor, other.micro))
+            .then_with(|| self.extra.is_none().cmp(&other.extra.is_none()))
+            .then_with(|| self.extra.cmp(&other.extra))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<&str> for Version {
+    fn from(value: &str) -> Self {
+        let end = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(value.len());
+        let (numbers, extra) = value.split_at(end);
+
+        let mut numbers = numbers.split('.').map(|x| x.parse().unwrap_or_default());
+
+        Self {
+            major: numbers.next().unwrap_or_default(),
+            minor: numbers.next().unwrap_or_default(),
+            micro: numbers.next().unwrap_or_default(),
+            extra: if extra.is_empty() {
+                None
+            } else {
+                Some(extra.to_string())
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)?;
+
+        if let Some(extra) = &self.extra {
+            write!(f, "{extra}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Optional features provided by the liblxc found at runtime.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    /** `Container::reboot2()` is available. */
+    pub reboot2: bool,
+    /** `Container::console_log()` is available. */
+    pub console_log: bool,
+    /** `Container::mount()` and `Container::umount()` are available. */
+    pub mount: bool,
+    /** `Container::seccomp_notify_fd()` is available. */
+    pub seccomp_notify: bool,
+    /** `Container::seccomp_notify_fd_active()` is available. */
+    pub seccomp_notify_active: bool,
+    /** `Container::init_pidfd()` is available. */
+    pub pidfd: bool,
+    /** `Container::devpts_fd()` is available. */
+    pub devpts_fd: bool,
+    /** `Container::set_timeout()` is available. */
+    pub set_timeout: bool,
+}
+
+impl Capabilities {
+    /**
+     * Determine the capabilities of the running liblxc.
+     *
+     * Capabilities tied to a configuration item also require liblxc to
+     * support it, the others only depend on the version. The result is
+     * computed once.
+     */
+    pub fn detect() -> crate::Result<Self> {
+        static CAPABILITIES: std::sync::OnceLock<Capabilities> = std::sync::OnceLock::new();
+
+        if let Some(capabilities) = CAPABILITIES.get() {
+            return Ok(*capabilities);
+        }
+
+        let capabilities = Self::from(&crate::version()?);
+
+        #[cfg(feature = "v2_1")]
+        let capabilities = {
+            let supported = |key| crate::config_item_is_supported(key);
+            let notify = supported("lxc.seccomp.notify.proxy")?;
+
+            Self {
+                reboot2: capabilities.reboot2 && supported("lxc.signal.reboot")?,
+                console_log: capabilities.console_log && supported("lxc.console.buffer.size")?,
+                seccomp_notify: capabilities.seccomp_notify && notify,
+                seccomp_notify_active: capabilities.seccomp_notify_active && notify,
+                ..capabilities
+            }
+        };
+
+        Ok(*CAPABILITIES.get_or_init(|| capabilities))
+    }
+
+    /**
+     * Fail with [`crate::Error::unsupported`] unless the running liblxc
+     * provides `api`.
+     *
+     * Calling a missing API would read past the end of the smaller
+     * `lxc_container` struct of an older liblxc.
+     */
+    #[cfg(feature = "v3_0")]
+    pub(crate) fn require(has: fn(&Self) -> bool, api: &str) -> crate::Result {
+        if has(&Self::detect()?) {
+            Ok(())
+        } else {
+            Err(crate::Error::unsupported(api))
+        }
+    }
+}
+
+impl From<&Version> for Capabilities {
+    fn from(version: &Version) -> Self {
+        Self {
+            reboot2: version.at_least(3, 0),
+            console_log: version.at_least(3, 0),
+            mount: version.at_least(3, 1),
+            seccomp_notify: version.at_least(3, 2),
+            seccomp_notify_active: version.at_least(5, 0),
+            pidfd: version.at_least(4, 0),
+            devpts_fd: version.at_least(5, 0),
+            set_timeout: version.at_least(6, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Capabilities, Version};
+
+    #[test]
+    fn from() {
+        let version = Version::from("6.0.1~git2209-g5a7b9ce67");
+
+        assert_eq!((version.major, version.minor, version.micro), (6, 0, 1));
+        assert_eq!(version.extra.as_deref(), Some("~git2209-g5a7b9ce67"));
+        assert_eq!(version.to_string(), "6.0.1~git2209-g5a7b9ce67");
+
+        assert_eq!(
+            Version::from("5.0"),
+            Version {
+                major: 5,
+                minor: 0,
+                micro: 0,
+                extra: None,
+            }
+        );
+        assert_eq!(Version::from(""), Version::default());
+        assert_eq!(Version::from("devel").extra.as_deref(), Some("devel"));
+    }
+
+    #[test]
+    fn at_least() {
+        assert!(Version::from("4.0.12").at_least(4, 0));
+        assert!(Version::from("4.0.12").at_least(3, 2));
+        assert!(!Version::from("4.0.12").at_least(4, 1));
+        assert!(Version::from("5.0.0-devel").at_least(5, 0));
+    }
+
+    #[test]
+    fn ord() {
+        assert!(Version::from("4.0.12") > Version::from("4.0.9"));
+        assert!(Version::from("5.0.0") > Version::from("4.9.9"));
+        assert!(Version::from("6.0.0-devel") < Version::from("6.0.0"));
+        assert!(Version::from("6.0.0-devel") > Version::from("5.0.3"));
+        assert!(Version::from("6.0.0~a") < Version::from("6.0.0~b"));
+    }
+
+    #[test]
+    fn capabilities() {
+        let capabilities = Capabilities::from(&Version::from("4.0.12"));
+
+        assert!(capabilities.reboot2 && capabilities.mount && capabilities.pidfd);
+        assert!(capabilities.seccomp_notify && !capabilities.seccomp_notify_active);
+        assert!(!capabilities.devpts_fd && !capabilities.set_timeout);
+
+        assert_eq!(
+            Capabilities::from(&Version::from("2.0.11")),
+            Capabilities::default()
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::os::fd::AsRawFd;
+
+/**
+ * System allocator counting the bytes still allocated by the current thread,
+ * other tests and the harness don't disturb the count.
+ */
+struct Counting;
+
+thread_local! {
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+fn count(delta: isize) {
+    LIVE.try_with(|live| live.set(live.get() + delta)).ok();
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size() as isize);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size() as isize);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(-(layout.size() as isize));
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size as isize - layout.size() as isize);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/**
+ * Bytes leaked by running `f` many times.
+ */
+fn leaked(f: impl Fn()) -> isize {
+    // Warm up lazily initialized statics
+    f();
+
+    let before = LIVE.with(Cell::get);
+
+    for _ in 0..1_000 {
+        f();
+    }
+
+    LIVE.with(Cell::get) - before
+}
+
+/**
+ * Container object outside of the default lxcpath, `None` without liblxc.
+ */
+fn container() -> Option<lxc::Container> {
+    lxc::load().ok()?;
+
+    Some(lxc::Container::open("allocations", Some(&std::env::temp_dir())).unwrap())
+}
+
+#[test]
+fn config_item() {
+    let Some(container) = container() else {
+        return;
+    };
+
+    assert_eq!(
+        leaked(|| {
+            container.set_config_item("lxc.arch", "x86_64").unwrap();
+            assert!(container.get_config_item("lxc.arch").unwrap().is_some());
+
+            assert!(container.set_config_item("lxc.nonexistent", "1").is_err());
+            assert!(container.get_config_item("lxc.nonexistent").is_err());
+            assert!(container.set_config_item("lxc.arch", "x86\0_64").is_err());
+        }),
+        0
+    );
+}
+
+#[test]
+fn wait() {
+    let Some(container) = container() else {
+        return;
+    };
+
+    assert_eq!(
+        leaked(|| {
+            assert!(container.wait("RUNNING", 0).is_err());
+            assert!(container.wait("RUN\0NING", 0).is_err());
+        }),
+        0
+    );
+}
+
+#[test]
+fn start() {
+    let Some(container) = container() else {
+        return;
+    };
+
+    assert_eq!(
+        leaked(|| {
+            assert!(container.start(false, &["/sbin/init", "--debug"]).is_err());
+            assert!(container.start(false, &["/sbin/in\0it"]).is_err());
+        }),
+        0
+    );
+}
+
+#[test]
+fn attach_run_wait() {
+    let Some(container) = container() else {
+        return;
+    };
+
+    let null = std::fs::File::open("/dev/null").unwrap();
+    let fd = null.as_raw_fd();
+
+    assert_eq!(
+        leaked(|| {
+            let mut options = lxc::attach::Options {
+                attach_flags: 0,
+                env_policy: 0,
+                extra_env_vars: std::ptr::null_mut(),
+                gid: 0,
+                uid: 0,
+                extra_keep_env: std::ptr::null_mut(),
+                initial_cwd: std::ptr::null_mut(),
+                #[cfg(feature = "v3_0")]
+                log_fd: fd,
+                stdout_fd: fd,
+                stderr_fd: fd,
+                stdin_fd: fd,
+                namespaces: -1,
+                personality: -1,
+                #[cfg(feature = "v5_0")]
+                groups: lxc_sys::lxc_groups_t {
+                    size: 0,
+                    list: std::ptr::null_mut(),
+                },
+                #[cfg(feature = "v5_0")]
+                lsm_label: std::ptr::null_mut(),
+            };
+
+            assert!(
+                container
+                    .attach_run_wait(&mut options, "/bin/true", &["/bin/true", "--help"])
+                    .is_err()
+            );
+        }),
+        0
+    );
+}
+
+#[test]
+fn log_init() {
+    assert_eq!(
+        leaked(|| {
+            let log = lxc::Log::builder()
+                .name("allocations")
+                .lxcpath("/nonexistent")
+                .file("/nonexistent/lxc.log")
+                .prefix("test")
+                .quiet(true)
+                .build();
+
+            log.init().ok();
+        }),
+        0
+    );
+}